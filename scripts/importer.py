@@ -48,6 +48,7 @@ async def main():
             print("Canary found. Not moving forth with import.")
             return
 
+        raw_pokemon = add_pokeapi_details(raw_pokemon)
         save_pokemon_to_db(db, raw_pokemon)
         create_pokemon_lists(db)
         create_draft_rules(db)
@@ -58,6 +59,106 @@ async def main():
 
     db.close()
 
+# PokeAPI's stat names to the short ones the backend uses
+STAT_NAMES = {
+    "hp": "hp",
+    "attack": "atk",
+    "defense": "def",
+    "special-attack": "spa",
+    "special-defense": "spd",
+    "speed": "spe",
+}
+
+FORM_KINDS = {"regional": "Regional", "mega": "Mega"}
+
+POKEAPI = "https://pokeapi.co/api/v2"
+
+# the gen a form came out in, its species' gen would put it in older sets
+FORM_GENS = {"mega": 6, "alola": 7, "galar": 8, "hisui": 8, "paldea": 9}
+
+def get_pokeapi(session, path):
+    resp = session.get(f"{POKEAPI}/{path}", timeout=10)
+    resp.raise_for_status()
+    return resp.json()
+
+def pokeapi_details(data):
+    return {
+        "stats": {s["stat"]["name"]: s["base_stat"] for s in data["stats"]},
+        "abilities": [
+            {"name": a["ability"]["name"], "is_hidden": a["is_hidden"]}
+            for a in data["abilities"]
+        ],
+    }
+
+# Only megas and regional forms are drafted, not gigantamax, totems or
+# cosmetic forms.
+def form_entry(pk, data):
+    form_name = data["name"].removeprefix(f"{pk['name']}-")
+    region = form_name.split("-")[0]
+    if region not in FORM_GENS:
+        return None
+
+    types = sorted(data["types"], key=lambda t: t["slot"])
+    is_mega = region == "mega"
+    return {
+        "id": str(data["id"]),
+        "name": data["name"],
+        "is_mythical": pk["is_mythical"],
+        "is_legendary": pk["is_legendary"],
+        "gen": FORM_GENS[region],
+        # a mega evolves from its species, a regional form from the line's pre-evolution
+        "evolves_from": pk["id"] if is_mega else pk["evolves_from"],
+        "type1": types[0]["type"]["name"],
+        "type2": types[1]["type"]["name"] if len(types) > 1 else "",
+        "form_of": pk["id"],
+        "form_type": "mega" if is_mega else "regional",
+        "form_name": form_name,
+        **pokeapi_details(data),
+    }
+
+# pokemon_models.json only has what every pokemon shares, stats, abilities
+# and forms come from PokeAPI. An entry that already has them keeps its own.
+def add_pokeapi_details(pokemon):
+    session = requests.Session()
+    forms = []
+    for pk in pokemon:
+        if pk.get("stats") or pk.get("form_of"):
+            continue
+        try:
+            pk.update(pokeapi_details(get_pokeapi(session, f"pokemon/{pk['id']}")))
+            species = get_pokeapi(session, f"pokemon-species/{pk['id']}")
+            for variety in species["varieties"]:
+                if variety["is_default"]:
+                    continue
+                form = form_entry(pk, get_pokeapi(session, variety["pokemon"]["url"].removeprefix(f"{POKEAPI}/")))
+                if form:
+                    forms.append(form)
+        except requests.exceptions.RequestException as e:
+            print(f"Unable to reach PokeAPI, the rest import without stats, abilities or forms: {e}")
+            break
+
+    return pokemon + forms
+
+# Stats, abilities and forms are optional. A form is its own entry, with an
+# id from 10001 up like PokeAPI gives it and the species it belongs to in
+# "form_of".
+def optional_fields(pk):
+    fields = {}
+    if pk.get("stats"):
+        fields["stats"] = {STAT_NAMES[name]: int(value) for name, value in pk["stats"].items()}
+    if pk.get("abilities"):
+        fields["abilities"] = [
+            {"name": a["name"], "is_hidden": bool(a.get("is_hidden", False))}
+            for a in pk["abilities"]
+        ]
+    if pk.get("form_of"):
+        fields["form"] = {
+            "species": int(pk["form_of"]),
+            "kind": FORM_KINDS.get(pk.get("form_type", ""), "Other"),
+            "name": pk.get("form_name", ""),
+        }
+    return fields
+
 # TODO maybe create a fixed ID for the initial sets?
 def save_pokemon_to_db(db, pokemon):
     for pk in pokemon:
@@ -73,7 +174,8 @@ def save_pokemon_to_db(db, pokemon):
                 "gen": int(pk['gen']),
                 "evolves_from": int(pk['evolves_from']), 
                 "type1": pk['type1'].upper(), 
-                "type2": pk['type2'].upper()
+                "type2": pk['type2'].upper(),
+                **optional_fields(pk)
             } 
         )
 
@@ -130,6 +232,24 @@ def create_pokemon_lists(db):
     result = db.create("pokemon_draft_set", {"name": "Debug Set"})
     db.query(f"RELATE {result['id']}->contains->({debug_sql})")
 
+    # evolved pokemon cost more, for trying out point budgets
+    base = "SELECT VALUE dex_id FROM pokemon WHERE evolves_from = 0"
+    result = db.create("pokemon_draft_set", {"name": "Debug Point Set"})
+    for points, stage in [
+        (3, "evolves_from = 0"),
+        (5, f"evolves_from IN ({base})"),
+        (8, f"evolves_from != 0 and evolves_from NOT IN ({base})"),
+    ]:
+        sql = f"SELECT id FROM pokemon WHERE dex_id < 10 and {stage}"
+        db.query(f"RELATE {result['id']}->contains->({sql}) SET points = {points}")
+
+    # final evolutions in OU, everything else in UU, for trying out tier limits
+    final = "SELECT VALUE evolves_from FROM pokemon WHERE evolves_from != 0"
+    result = db.create("pokemon_draft_set", {"name": "Debug Tier Set"})
+    for tier, stage in [("OU", f"dex_id NOT IN ({final})"), ("UU", f"dex_id IN ({final})")]:
+        sql = f"SELECT id FROM pokemon WHERE dex_id < 10 and {stage}"
+        db.query(f"RELATE {result['id']}->contains->({sql}) SET tier = '{tier}'")
+
 def create_draft_rules(db):
     db.create("draft_rules", {
         "name": "Showdown Snake",
@@ -194,6 +314,35 @@ def create_draft_rules(db):
         "turn_type": "RoundRobin"
     })
 
+    db.create("draft_rules", {
+        "name": "Intergration Test Point Budget",
+        "picks_per_round": 1,
+        "bans_per_round": 0,
+        "max_pokemon": 2,
+        "starting_phase": "Pick",
+        "turn_type": "Snake",
+        "point_budget": 11
+    })
+
+    db.create("draft_rules", {
+        "name": "Intergration Test Tiers",
+        "picks_per_round": 1,
+        "bans_per_round": 0,
+        "max_pokemon": 2,
+        "starting_phase": "Pick",
+        "turn_type": "Snake",
+        "tier_limits": [{"tier": "OU", "max_picks": 1}]
+    })
+
+    db.create("draft_rules", {
+        "name": "Intergration Test Two Picks",
+        "picks_per_round": 1,
+        "bans_per_round": 0,
+        "max_pokemon": 2,
+        "starting_phase": "Pick",
+        "turn_type": "Snake"
+    })
+
 if __name__ == "__main__":
     import asyncio
     asyncio.run(main())
@@ -0,0 +1,234 @@
+//! Player and host authentication. Joining a session hands the player a token,
+//! `<draft_user key>.<secret>`, and creating one hands the creator a host token.
+//! Either goes back in an `Authorization: Bearer` header on the routes that
+//! change the session. Draft sets work like sessions, with an owner token.
+use crate::api::draft_set::set_not_found;
+use crate::api::error::{ApiError, ApiResult};
+use crate::models::draft::{DraftSession, DraftUser};
+use crate::store::{Store, DRAFT_USER_TB};
+
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Request, State};
+
+use surrealdb::RecordId;
+
+use uuid::Uuid;
+
+pub fn player_token(user_key: &str, secret: &Uuid) -> String {
+    format!("{user_key}.{secret}")
+}
+
+fn parse_token(token: &str) -> ApiResult<(RecordId, Uuid)> {
+    let invalid = || ApiError::unauthorized("invalid_token", "Malformed player token");
+
+    let (user_key, secret) = token.split_once('.').ok_or_else(invalid)?;
+    if user_key.is_empty() {
+        return Err(invalid());
+    }
+    let secret = Uuid::parse_str(secret).map_err(|_| invalid())?;
+
+    Ok((RecordId::from_table_key(DRAFT_USER_TB, user_key), secret))
+}
+
+/// A player whose token checked out, along with the session it was checked
+/// against.
+pub struct AuthenticatedPlayer {
+    pub session_id: String,
+    pub session: DraftSession,
+    pub user_id: RecordId,
+    pub player: DraftUser,
+}
+
+/// Loads the session and finds the player `token` belongs to. Keys still
+/// stored with the old hash are re-hashed on the way through.
+pub async fn authenticate(db: &Store, session_id: &str, token: &str) -> ApiResult<AuthenticatedPlayer> {
+    let (user_id, secret) = parse_token(token)?;
+
+    let session = match db.get_session(session_id).await? {
+        Some(s) => s,
+        None => return Err(session_not_found()),
+    };
+
+    let mut player = match session.get_player(&user_id) {
+        Some(p) => p.clone(),
+        None => return Err(ApiError::not_found("user_not_found", "User not in session.")),
+    };
+
+    if !player.check_key(&secret) {
+        return Err(ApiError::unauthorized("access_denied", "Access Denied"));
+    }
+
+    if let Some(key_hash) = player.upgrade_key_hash(&secret) {
+        db.set_key_hash(&user_id, key_hash).await?;
+    }
+
+    Ok(AuthenticatedPlayer {
+        session_id: session_id.to_string(),
+        session,
+        user_id,
+        player,
+    })
+}
+
+fn bearer_token<'r>(req: &'r Request<'_>) -> ApiResult<&'r str> {
+    let header = match req.headers().get_one("Authorization") {
+        Some(h) => h,
+        None => return Err(ApiError::unauthorized("missing_token", "Authorization header is required")),
+    };
+
+    match header.split_once(' ') {
+        Some((scheme, token)) if scheme.eq_ignore_ascii_case("bearer") => Ok(token.trim()),
+        _ => Err(ApiError::unauthorized("invalid_token", "Expected a Bearer token")),
+    }
+}
+
+/// Id, bearer token and store for a guard. Only for routes shaped
+/// `/draft_session/<id>/...` or `/draft_set/<id>`, the id is read from the
+/// second path segment.
+async fn guard_inputs<'r>(
+    req: &'r Request<'_>,
+    not_found: fn() -> ApiError,
+) -> ApiResult<(&'r str, &'r str, &'r Store)> {
+    let id = match req.param::<&str>(1) {
+        Some(Ok(id)) => id,
+        _ => return Err(not_found()),
+    };
+    let token = bearer_token(req)?;
+    let db = match req.guard::<&State<Store>>().await {
+        Outcome::Success(db) => db.inner(),
+        _ => return Err(ApiError::internal("Draft store is not configured")),
+    };
+
+    Ok((id, token, db))
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AuthenticatedPlayer {
+    type Error = ApiError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ApiError> {
+        let result = match guard_inputs(req, session_not_found).await {
+            Ok((session_id, token, db)) => authenticate(db, session_id, token).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(player) => Outcome::Success(player),
+            Err(e) => e.guard_failure(req),
+        }
+    }
+}
+
+/// The host of the session, proven by the host token from `create_draft_session`.
+pub struct SessionHost {
+    pub session_id: String,
+    pub session: DraftSession,
+}
+
+fn session_not_found() -> ApiError {
+    ApiError::not_found("session_not_found", "Session not found")
+}
+
+fn draft_set_not_found() -> ApiError {
+    ApiError::not_found("draft_set_not_found", "Draft set not found")
+}
+
+fn not_host() -> ApiError {
+    ApiError::forbidden("not_host", "Only the session host can do that")
+}
+
+/// Sessions made before hosts existed are run by their first player, who uses
+/// their player token.
+pub async fn authenticate_host(db: &Store, session_id: &str, token: &str) -> ApiResult<SessionHost> {
+    let session = match db.get_session(session_id).await? {
+        Some(s) => s,
+        None => return Err(session_not_found()),
+    };
+
+    if session.host_key_hash.is_none() {
+        let player = authenticate(db, session_id, token).await?;
+        if player.player.order_in_session != 0 {
+            return Err(not_host());
+        }
+        return Ok(SessionHost { session_id: player.session_id, session: player.session });
+    }
+
+    match Uuid::parse_str(token) {
+        Ok(key) if session.check_host_key(&key) => Ok(SessionHost {
+            session_id: session_id.to_string(),
+            session,
+        }),
+        _ => Err(not_host()),
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SessionHost {
+    type Error = ApiError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ApiError> {
+        let result = match guard_inputs(req, session_not_found).await {
+            Ok((session_id, token, db)) => authenticate_host(db, session_id, token).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(host) => Outcome::Success(host),
+            Err(e) => e.guard_failure(req),
+        }
+    }
+}
+
+/// Whoever created the draft set, proven by the owner token `create_draft_set`
+/// handed out.
+pub struct DraftSetOwner {
+    pub set_id: String,
+}
+
+pub async fn authenticate_set_owner(db: &Store, set_id: &str, token: &str) -> ApiResult<DraftSetOwner> {
+    let set = match db.get_draft_set(set_id, false).await? {
+        Some(s) => s,
+        None => return Err(set_not_found(set_id)),
+    };
+
+    match (Uuid::parse_str(token), &set.owner_key_hash) {
+        (Ok(key), Some(hash)) if hash.verify(&key) => Ok(DraftSetOwner { set_id: set_id.to_string() }),
+        _ => Err(ApiError::forbidden("not_owner", "Only whoever created the draft set can change it")),
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for DraftSetOwner {
+    type Error = ApiError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ApiError> {
+        let result = match guard_inputs(req, draft_set_not_found).await {
+            Ok((set_id, token, db)) => authenticate_set_owner(db, set_id, token).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(owner) => Outcome::Success(owner),
+            Err(e) => e.guard_failure(req),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_token_round_trip() {
+        let secret = Uuid::new_v4();
+        let token = player_token("abc123", &secret);
+
+        let (user_id, parsed) = parse_token(&token).unwrap();
+        assert_eq!(user_id, RecordId::from_table_key(DRAFT_USER_TB, "abc123"));
+        assert_eq!(parsed, secret);
+
+        assert!(parse_token("abc123").is_err());
+        assert!(parse_token(".not-a-uuid").is_err());
+        assert!(parse_token(&format!(".{secret}")).is_err());
+    }
+}
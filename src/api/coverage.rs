@@ -0,0 +1,26 @@
+//! Type coverage of a player's roster, so players can see the gaps in their
+//! team while the draft is still going. Rosters are public already through
+//! the update endpoint, so this needs no token.
+use std::sync::Arc;
+
+use crate::api::draft_session::{find_player, find_session};
+use crate::api::error::ApiResult;
+use crate::models::pokedex::Pokedex;
+use crate::models::type_chart::TeamCoverage;
+use crate::store::Store;
+
+use rocket::serde::json::Json;
+use rocket::State;
+
+#[get("/draft_session/<id>/players/<user>/coverage")]
+pub async fn get_coverage(
+    id: &str,
+    user: &str,
+    db: &State<Store>,
+    pokedex: &State<Arc<Pokedex>>,
+) -> ApiResult<Json<TeamCoverage>> {
+    let session = find_session(id, db).await?;
+    let player = find_player(&session, user)?;
+
+    Ok(Json(TeamCoverage::new(&pokedex.roster(&player.selected_pokemon))))
+}
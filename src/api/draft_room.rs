@@ -0,0 +1,356 @@
+//! WebSocket draft room, `GET /draft_session/<id>/room`.
+//!
+//! Every message is a JSON text frame tagged with `type`.
+//!
+//! Client -> server:
+//!
+//! ```json
+//! {"type": "auth", "token": "<token from create-user>"}
+//! {"type": "select", "pokemon_id": 25, "action": "Pick" | "Ban"}
+//! {"type": "ready"}
+//! {"type": "chat", "message": "gl hf"}
+//! ```
+//!
+//! `auth` has to be the first message, anything else before it gets an error back.
+//!
+//! Server -> client:
+//!
+//! ```json
+//! {"type": "welcome", "player": "Ash", "state": <same body as GET /draft_session/<id>/update>}
+//! {"type": "event", "seq": 7, "event": {"type": "pokemon_picked", "player": "Ash", "pokemon_id": 25}}
+//! {"type": "resync", "state": <same body as GET /draft_session/<id>/update>}
+//! {"type": "error", "code": "not_your_turn", "message": "It is not your turn", "details": null}
+//! ```
+//!
+//! After `welcome` the state only changes through `event` messages, the same
+//! events the SSE stream sends, so applying them to the snapshot keeps a client
+//! in sync. Only events the snapshot doesn't already show are sent, those with
+//! a `seq` past its `action_count`; chat has no `seq`. A connection that falls
+//! too far behind gets a `resync` with the whole state in place of the events
+//! it missed. Errors only go to the connection that caused them.
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::api::auth::{self, AuthenticatedPlayer};
+use crate::api::draft_session::{apply_ready_toggle, apply_selection, SelectPokemonRequest, UpdateDraftSessionResponse};
+use crate::api::error::{ApiError, ApiResult, ErrorBody};
+use crate::events::{DraftEvent, EventHub, SessionEvent};
+use crate::models::draft::DraftPhase;
+use crate::models::pokedex::Pokedex;
+use crate::store::Store;
+
+use rocket::data::{IoHandler, IoStream};
+use rocket::futures::{SinkExt, StreamExt};
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome};
+use rocket::response::{self, Responder};
+use rocket::serde::json::serde_json;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{Request, Response, State};
+
+use serde::{Deserialize, Serialize};
+
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+const MAX_CHAT_LEN: usize = 500;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Auth { token: String },
+    Select { pokemon_id: u32, action: DraftPhase },
+    Ready,
+    Chat { message: String },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome { player: String, state: UpdateDraftSessionResponse },
+    Event {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        seq: Option<u32>,
+        event: DraftEvent,
+    },
+    Resync { state: UpdateDraftSessionResponse },
+    Error(ErrorBody),
+}
+
+impl From<ApiError> for ServerMessage {
+    fn from(e: ApiError) -> ServerMessage {
+        ServerMessage::Error(e.into_body())
+    }
+}
+
+/// Request guard for the websocket handshake headers.
+pub struct WebSocketUpgrade {
+    accept_key: String,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for WebSocketUpgrade {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, ()> {
+        let headers = req.headers();
+        let is_upgrade = headers
+            .get_one("Upgrade")
+            .map(|u| u.eq_ignore_ascii_case("websocket"))
+            .unwrap_or(false);
+        let version_ok = headers.get_one("Sec-WebSocket-Version") == Some("13");
+
+        match headers.get_one("Sec-WebSocket-Key") {
+            Some(key) if is_upgrade && version_ok => Outcome::Success(WebSocketUpgrade {
+                accept_key: derive_accept_key(key.as_bytes()),
+            }),
+            _ => Outcome::Error((Status::BadRequest, ())),
+        }
+    }
+}
+
+pub struct DraftRoom {
+    accept_key: String,
+    session_id: String,
+    db: Store,
+    hub: EventHub,
+    pokedex: Arc<Pokedex>,
+}
+
+impl<'r> Responder<'r, 'static> for DraftRoom {
+    fn respond_to(self, _req: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .raw_header("Sec-WebSocket-Accept", self.accept_key.clone())
+            .upgrade("websocket", self)
+            .ok()
+    }
+}
+
+#[get("/draft_session/<id>/room")]
+pub async fn draft_room(
+    id: &str,
+    upgrade: WebSocketUpgrade,
+    db: &State<Store>,
+    hub: &State<EventHub>,
+    pokedex: &State<Arc<Pokedex>>,
+) -> ApiResult<DraftRoom> {
+    // 404 before switching protocols instead of after
+    if db.get_session(id).await?.is_none() {
+        return Err(ApiError::not_found("session_not_found", "Session not found"));
+    }
+
+    Ok(DraftRoom {
+        accept_key: upgrade.accept_key,
+        session_id: id.to_string(),
+        db: db.inner().clone(),
+        hub: hub.inner().clone(),
+        pokedex: pokedex.inner().clone(),
+    })
+}
+
+/// The player this connection authenticated as.
+struct Seat {
+    token: String,
+    name: String,
+}
+
+type Socket = WebSocketStream<IoStream>;
+
+async fn send(ws: &mut Socket, msg: &ServerMessage) -> io::Result<()> {
+    let text = serde_json::to_string(msg).map_err(io::Error::other)?;
+    ws.send(Message::Text(text)).await.map_err(io::Error::other)
+}
+
+impl DraftRoom {
+    /// The seat along with the snapshot for its `welcome`.
+    async fn authenticate(&self, token: String) -> ApiResult<(Seat, UpdateDraftSessionResponse)> {
+        let player = auth::authenticate(&self.db, &self.session_id, &token).await?;
+        let name = player.player.name;
+
+        Ok((Seat { token, name }, UpdateDraftSessionResponse::from(player.session)))
+    }
+
+    async fn snapshot(&self) -> ApiResult<UpdateDraftSessionResponse> {
+        match self.db.get_session(&self.session_id).await? {
+            Some(session) => Ok(UpdateDraftSessionResponse::from(session)),
+            None => Err(ApiError::not_found("session_not_found", "Session not found")),
+        }
+    }
+
+    /// Checks the seat's token again so every message acts on a fresh session.
+    async fn reauthenticate(&self, seat: &Seat) -> ApiResult<AuthenticatedPlayer> {
+        auth::authenticate(&self.db, &self.session_id, &seat.token).await
+    }
+
+    async fn handle(&self, seat: &Seat, msg: ClientMessage) -> ApiResult<()> {
+        match msg {
+            ClientMessage::Auth { .. } => Err(ApiError::conflict("already_authenticated", "Already authenticated")),
+            ClientMessage::Select { pokemon_id, action } => {
+                let player = self.reauthenticate(seat).await?;
+                let request = SelectPokemonRequest { pokemon_id, action };
+                apply_selection(&self.db, &self.hub, &self.pokedex, player, request).await.map(|_| ())
+            },
+            ClientMessage::Ready => {
+                let player = self.reauthenticate(seat).await?;
+                apply_ready_toggle(&self.db, &self.hub, player).await
+            },
+            ClientMessage::Chat { message } => {
+                let player = self.reauthenticate(seat).await?;
+                let message = message.trim();
+                if message.is_empty() || message.chars().count() > MAX_CHAT_LEN {
+                    return Err(ApiError::bad_request(
+                        "invalid_chat_message",
+                        format!("Chat messages must be between 1 and {MAX_CHAT_LEN} characters"),
+                    ));
+                }
+
+                self.hub.publish(&self.session_id, None, vec![DraftEvent::ChatMessage {
+                    player: player.player.name,
+                    message: message.to_string(),
+                }]);
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Reads the next text frame as a [`ClientMessage`]. `None` once the socket closes.
+async fn next_message(ws: &mut Socket) -> Option<Result<ClientMessage, ApiError>> {
+    loop {
+        let text = match ws.next().await? {
+            Ok(Message::Text(t)) => t,
+            Ok(Message::Close(_)) | Err(_) => return None,
+            // pings are answered by tungstenite itself
+            Ok(_) => continue,
+        };
+
+        return Some(serde_json::from_str(&text).map_err(|e| {
+            ApiError::bad_request("invalid_message", "Could not parse message").with_details(e.to_string().into())
+        }));
+    }
+}
+
+#[rocket::async_trait]
+impl IoHandler for DraftRoom {
+    async fn io(self: Pin<Box<Self>>, io: IoStream) -> io::Result<()> {
+        let mut ws = WebSocketStream::from_raw_socket(io, Role::Server, None).await;
+
+        let (seat, mut rx, mut seen) = loop {
+            let msg = match next_message(&mut ws).await {
+                Some(m) => m,
+                None => return Ok(()),
+            };
+
+            let token = match msg {
+                Ok(ClientMessage::Auth { token }) => token,
+                Ok(_) => {
+                    send(&mut ws, &ApiError::unauthorized("not_authenticated", "Send an auth message first").into()).await?;
+                    continue;
+                },
+                Err(e) => {
+                    send(&mut ws, &e.into()).await?;
+                    continue;
+                },
+            };
+
+            // subscribed before the snapshot is read so nothing falls in between,
+            // what lands in both is skipped by its seq
+            let rx = self.hub.subscribe(&self.session_id);
+            match self.authenticate(token).await {
+                Ok((seat, state)) => {
+                    let seen = state.action_count;
+                    send(&mut ws, &ServerMessage::Welcome { player: seat.name.clone(), state }).await?;
+                    break (seat, rx, seen);
+                },
+                Err(e) => send(&mut ws, &e.into()).await?,
+            }
+        };
+
+        loop {
+            select! {
+                msg = next_message(&mut ws) => {
+                    let result = match msg {
+                        Some(Ok(m)) => self.handle(&seat, m).await,
+                        Some(Err(e)) => Err(e),
+                        None => break,
+                    };
+
+                    if let Err(e) = result {
+                        send(&mut ws, &e.into()).await?;
+                    }
+                },
+                event = rx.recv() => match event {
+                    Ok(event) if event.seen_at(seen) => continue,
+                    Ok(SessionEvent { seq, event }) => send(&mut ws, &ServerMessage::Event { seq, event }).await?,
+                    // events were dropped, hand over the whole state instead
+                    Err(RecvError::Lagged(_)) => match self.snapshot().await {
+                        Ok(state) => {
+                            seen = state.action_count;
+                            send(&mut ws, &ServerMessage::Resync { state }).await?;
+                        },
+                        Err(e) => {
+                            send(&mut ws, &e.into()).await?;
+                            break;
+                        },
+                    },
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_client_message_schema() {
+        let msg: ClientMessage = serde_json::from_str(r#"{"type": "select", "pokemon_id": 25, "action": "Ban"}"#).unwrap();
+        assert!(matches!(msg, ClientMessage::Select { pokemon_id: 25, action: DraftPhase::Ban }));
+
+        let msg: ClientMessage = serde_json::from_str(r#"{"type": "ready"}"#).unwrap();
+        assert!(matches!(msg, ClientMessage::Ready));
+
+        assert!(serde_json::from_str::<ClientMessage>(r#"{"type": "pick", "pokemon_id": 25}"#).is_err());
+    }
+
+    #[test]
+    fn test_server_error_shape() {
+        let msg: ServerMessage = ApiError::forbidden("not_your_turn", "It is not your turn").into();
+        assert_eq!(
+            serde_json::to_value(&msg).unwrap(),
+            serde_json::json!({
+                "type": "error",
+                "code": "not_your_turn",
+                "message": "It is not your turn",
+                "details": null,
+            })
+        );
+    }
+
+    #[test]
+    fn test_server_event_shape() {
+        let msg = ServerMessage::Event { seq: Some(7), event: DraftEvent::DraftStarted };
+        assert_eq!(
+            serde_json::to_value(&msg).unwrap(),
+            serde_json::json!({ "type": "event", "seq": 7, "event": { "type": "draft_started" } })
+        );
+
+        // chat isn't part of the session's state, so it has no seq
+        let msg = ServerMessage::Event {
+            seq: None,
+            event: DraftEvent::ChatMessage { player: "Ash".into(), message: "gl hf".into() },
+        };
+        assert_eq!(
+            serde_json::to_value(&msg).unwrap(),
+            serde_json::json!({ "type": "event", "event": { "type": "chat_message", "player": "Ash", "message": "gl hf" } })
+        );
+    }
+}
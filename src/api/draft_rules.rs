@@ -1,68 +1,41 @@
-use crate::models::Record;
+use crate::api::error::{ApiError, ApiResult};
 use crate::models::draft::DraftRules;
+use crate::store::Store;
 
 use rocket::State;
 use rocket::serde::json::Json;
 
-use surrealdb::Surreal;
-use surrealdb::engine::remote::ws::Client;
-
 // TODO: Move these out and get draft rules based on name too?
 #[get("/draft_rules/<id>")]
-pub async fn get_draft_rules(id: &str, db: &State<Surreal<Client>>) -> Option<Json<DraftRules>> {
-    let rules: Option<DraftRules> = match db.select(("draft_rules", id)).await {
-        Ok(p) => p,
-        Err(e) => {
-            println!("{}", e);
-            None
-        }
-    };
-
-    match rules {
-        Some(r) => Some(Json(r)),
-        None => None,
+pub async fn get_draft_rules(id: &str, db: &State<Store>) -> ApiResult<Json<DraftRules>> {
+    match db.get_draft_rules(id).await? {
+        Some(r) => Ok(Json(r)),
+        None => Err(ApiError::not_found("draft_rules_not_found", format!("No draft rules with id {id}"))),
     }
 }
 
 #[get("/draft_rules")]
-pub async fn list_draft_rules(db: &State<Surreal<Client>>) -> Json<Vec<DraftRules>> {
-    let draft_sets = match db.select("draft_rules").await {
-        Ok(p) => p,
-        Err(e) => {
-            println!("{}", e);
-            Vec::new()
-        }
-    };
+pub async fn list_draft_rules(db: &State<Store>) -> ApiResult<Json<Vec<DraftRules>>> {
+    let draft_sets = db.list_draft_rules().await?;
 
-    Json(draft_sets)
+    Ok(Json(draft_sets))
 }
 
 #[post("/draft_rules/create", format = "application/json", data = "<dr_form>")]
 pub async fn create_draft_rules(
     dr_form: Json<DraftRules>,
-    db: &State<Surreal<Client>>,
-) -> Option<String> {
+    db: &State<Store>,
+) -> ApiResult<String> {
     // should you even do this?
     let draft_rules: DraftRules = dr_form.0;
+    if let Err(message) = draft_rules.check_turn_order() {
+        return Err(ApiError::bad_request("invalid_turn_order", message));
+    }
 
-    let result: Vec<Record> = match db.create("draft_rules").content(draft_rules).await {
-        Ok(Some(r)) => r,
-        Ok(None) => {
-            return None;
-        }
-        Err(e) => {
-            println!("{}", e);
-            return None;
-        }
-    };
-
-    let record = if result.len() > 0 {
-        format!("{{\"id\": \"{}\"}}", result[0].id)
-    } else {
-        "{\"message\": \"Could not create Draft Rule\"}".into()
-    };
+    let result: DraftRules = db.create_draft_rules(draft_rules).await?;
 
-    Some(record)
+    match result.id {
+        Some(id) => Ok(format!("{{\"id\": \"{}\"}}", id)),
+        None => Err(ApiError::internal("Could not create Draft Rule")),
+    }
 }
-
-
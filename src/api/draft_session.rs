@@ -1,46 +1,101 @@
-use crate::api::utils::{relate_objects, run_query};
+use std::sync::Arc;
+
+use crate::api::auth::{player_token, AuthenticatedPlayer, SessionHost};
+use crate::api::error::{ApiError, ApiResult};
+use crate::events::EventHub;
+use crate::models::draft::engine::{self, DraftAction};
+use crate::models::draft::history::{self, Actor, DraftEventRecord};
 use crate::models::draft::{
-    DraftPhase, DraftRules, DraftSession, DraftSessionCreateForm, DraftState, DraftUser, DraftUserForm, DraftUserReturnData
+    DraftPhase, DraftSession, DraftSessionCreateForm, DraftState, DraftUser, DraftUserForm, DraftUserReturnData,
+    Selection,
 };
-use crate::models::pokemon::PokemonType;
-use crate::models::{hash_uuid, Record};
+use crate::models::key::KeyHash;
+use crate::models::pokedex::Pokedex;
+use crate::store::{self, Store, DRAFT_USER_TB};
+use crate::timer::now_ms;
 
-use rocket::response::status::NotFound;
+use rocket::response::stream::{Event, EventStream};
 use rocket::serde::json::Json;
-use rocket::State;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{Shutdown, State};
 
 use serde::{Deserialize, Serialize};
 
-use surrealdb::{RecordId, Surreal};
-use surrealdb::engine::remote::ws::Client;
+use surrealdb::RecordId;
 
 use uuid::Uuid;
 
-const DRAFT_USER_RELATION: &str = "players";
-const DRAFT_SESSION: &str = "draft_session";
-const DRAFT_USER_TB: &str = "draft_user";
-
 fn to_json_msg(str: &str) -> String {
     format!("{{\"message\": \"{}\"}}", str)
 }
 
+pub(crate) async fn find_session(id: &str, db: &Store) -> ApiResult<DraftSession> {
+    match db.get_session(id).await? {
+        Some(s) => Ok(s),
+        None => Err(ApiError::not_found("session_not_found", "Session not found")),
+    }
+}
+
+/// `user` is the key of the player's record id.
+pub(crate) fn find_player<'a>(session: &'a DraftSession, user: &str) -> ApiResult<&'a DraftUser> {
+    match session.get_player(&RecordId::from_table_key(DRAFT_USER_TB, user)) {
+        Some(p) => Ok(p),
+        None => Err(ApiError::not_found("user_not_found", "User not in session.")),
+    }
+}
+
+fn session_changed() -> ApiError {
+    ApiError::conflict("session_changed", "The session changed while this was being handled, try again")
+}
+
+/// Runs `action` through the draft engine and saves the session along with the
+/// players it changed, logs it, then lets subscribers know what happened. Fails
+/// with `session_changed`, without saving anything, if another action was saved
+/// since `session` was read.
+async fn run_action(
+    db: &Store,
+    hub: &EventHub,
+    id: &str,
+    session: DraftSession,
+    actor: Actor,
+    action: DraftAction,
+) -> ApiResult<DraftSession> {
+    let (before, turn, now) = (session.players.clone().unwrap_or_default(), session.turn_ticker, now_ms());
+    let action_count = session.action_count;
+    let (session, events) = engine::apply(session, action.clone(), now)?;
+
+    if !db.update_session_if(&session, action_count).await? {
+        return Err(session_changed());
+    }
+    store::save_players(db, &before, session.players.as_deref().unwrap_or_default()).await?;
+    if let Some(record) = DraftEventRecord::new(turn, &session, &action, actor, now, &events) {
+        db.add_draft_event(record).await?;
+    }
+    hub.publish(id, Some(session.action_count), events);
+
+    Ok(session)
+}
+
+/// Strips what only the server should see before a session goes out: the
+/// host's key hash, and the seed that would tell a `RandomPerRound` draft's
+/// upcoming turns.
+fn hide_secrets(mut session: DraftSession) -> DraftSession {
+    session.host_key_hash = None;
+    session.order_seed = 0;
+    session
+}
+
 #[get("/draft_session/<id>")]
 pub async fn get_draft_session(
     id: &str,
-    db: &State<Surreal<Client>>,
-) -> Option<Json<DraftSession>> {
-    let session: Option<DraftSession> = match db.select(("draft_session", id)).await {
-        Ok(p) => p,
-        Err(e) => {
-            println!("{}", e);
-            None
-        }
-    };
+    db: &State<Store>,
+) -> ApiResult<Json<DraftSession>> {
+    let mut session = find_session(id, db).await?;
 
-    match session {
-        Some(ds) => Some(Json(ds)),
-        None => None,
-    }
+    // players are only exposed through the update endpoint
+    session.players = None;
+    Ok(Json(hide_secrets(session)))
 }
 
 #[options("/draft_session/create")]
@@ -55,167 +110,296 @@ pub fn option_draft_session<'a>() -> &'a str {
 )]
 pub async fn create_draft_session(
     session_form: Json<DraftSessionCreateForm>,
-    db: &State<Surreal<Client>>,
-) -> Option<Json<DraftSession>> {
+    db: &State<Store>,
+) -> ApiResult<Json<CreateDraftSessionResponse>> {
     // should you even do this?
     let session_form: DraftSessionCreateForm = session_form.0;
 
-    let rules: DraftRules = match db.select(("draft_rules", &session_form.draft_rules)).await {
-        Ok(p) => match p {
-            Some(r) => r,
-            None => return None,
-        },
-        Err(e) => {
-            println!("BLAGH: {}", e);
-            return None;
+    let rules = match db.get_draft_rules(&session_form.draft_rules).await? {
+        Some(r) => r,
+        None => {
+            return Err(ApiError::not_found(
+                "draft_rules_not_found",
+                format!("No draft rules with id {}", session_form.draft_rules),
+            ))
         }
     };
 
-    let draft_session = DraftSession::from(session_form, rules);
-    let result: DraftSession = match db.create("draft_session").content(draft_session).await {
-        Ok(Some(r)) => r,
-        Ok(None) => return None,
-        Err(e) => {
-            println!("HELLO: {}", e);
-            return None;
+    let draft_pool = match store::draft_set_members(db, &session_form.draft_set).await? {
+        Some(members) => members,
+        None => {
+            return Err(ApiError::not_found(
+                "draft_set_not_found",
+                format!("No draft set with id {}", session_form.draft_set),
+            ))
         }
     };
 
-    Some(Json(result))
+    let host_key = Uuid::new_v4();
+    let mut draft_session = DraftSession::from(session_form, rules);
+    draft_session.draft_pool = Some(draft_pool);
+    draft_session.order_seed = rand::random();
+    draft_session.host_key_hash = Some(KeyHash::new(&host_key));
+
+    let session = hide_secrets(db.create_session(draft_session).await?);
+
+    Ok(Json(CreateDraftSessionResponse {
+        session,
+        host_token: host_key.to_string(),
+    }))
+}
+
+#[options("/draft_session/<id>/ready")]
+pub fn option_ready<'a>(id: &str) -> &'a str {
+    let _id = id;
+    "Ok"
 }
 
 #[post(
     "/draft_session/<id>/ready",
-    format = "application/json",
-    data = "<user_form>"
+    format = "application/json"
 )]
 pub async fn toggle_ready(
     id: &str,
-    user_form: Json<ReadyDraftUserForm>,
-    db: &State<Surreal<Client>>,
-) -> Result<String, NotFound<String>> {
-    let query =
-        format!("SELECT *,(SELECT * from ->{DRAFT_USER_RELATION}.out ORDER BY order_in_session ASC) as players FROM draft_session:{id};");
-
-    let session: DraftSession = match run_query(query, db).await {
-        Some(s) => s,
-        None => return Err(NotFound("Session not found".into())),
-    };
-
-    if session.draft_state == DraftState::InProgress || session.draft_state == DraftState::Ended {
-        // TODO: Set error message
-        return Err(NotFound(to_json_msg(
-            "Can't ready when the draft is in progress.",
-        )));
-    }
-
-    // Get the user
-    let user_id = RecordId::from_table_key(DRAFT_USER_TB, user_form.0.user_id);
-    let players = match session.players {
-        Some(p) => p,
-        None => {
-            return Err(NotFound(to_json_msg(
-                "Can't ready when no players are in session",
-            )))
-        }
-    };
-
-    let mut all_players_ready = true;
-    for player in players.iter() {
-        if let Some(p_id) = &player.id {
-            if p_id != &user_id {
-                all_players_ready = player.ready & all_players_ready
-            }
-        }
-    }
-
-    let user = match get_current_player(players, &user_id) {
-        Some(u) => u,
-        None => {
-            return Err(NotFound(to_json_msg(
-                "Unable to get current player.",
-            )))
-        }
-    };
+    player: AuthenticatedPlayer,
+    db: &State<Store>,
+    hub: &State<EventHub>,
+) -> ApiResult<String> {
+    let _id = id;
+    apply_ready_toggle(db, hub, player).await?;
 
-    let ready = !user.ready;
-    all_players_ready = all_players_ready && ready;
-    let new_draft_state = if all_players_ready {
-        DraftState::Ready
-    } else {
-        DraftState::Open
-    };
+    Ok(to_json_msg("All good"))
+}
 
-    #[derive(Serialize)]
-    struct UpdateData {
-        ready: bool,
-    }
-    let update = UpdateData { ready };
-    let _updated: Option<Record> = db
-        .update(user_id)
-        .merge(update)
-        .await
-        .map_err(|e| NotFound(e.to_string()))?;
-
-    #[derive(Serialize)]
-    struct SessionUpdateData {
-        draft_state: DraftState,
-    }
-    let update = SessionUpdateData {
-        draft_state: new_draft_state,
-    };
-    // Maybe use set to session_id so that it's easier to tell what the id is for
-    let _updated: Option<Record> = db
-        .update((DRAFT_SESSION, id))
-        .merge(update)
-        .await
-        .map_err(|e| NotFound(e.to_string()))?;
+/// Flips the player's ready flag. Shared by the HTTP route and the draft room socket.
+pub(crate) async fn apply_ready_toggle(
+    db: &Store,
+    hub: &EventHub,
+    player: AuthenticatedPlayer,
+) -> ApiResult<()> {
+    let action = DraftAction::ToggleReady { user_id: player.user_id.clone() };
+    let actor = Actor::Player { name: player.player.name };
+    run_action(db, hub, &player.session_id, player.session, actor, action).await?;
+
+    Ok(())
+}
 
-    Ok(to_json_msg("All good"))
+#[options("/draft_session/<id>/start")]
+pub fn option_start<'a>(id: &str) -> &'a str {
+    let _id = id;
+    "Ok"
 }
 
-// TODO: This can start at any time?
 #[post(
     "/draft_session/<id>/start",
     format = "application/json"
 )]
 pub async fn start(
     id: &str,
-    db: &State<Surreal<Client>>,
-) -> Result<String, NotFound<String>> {
-    #[derive(Serialize)]
-    struct SessionUpdateData {
-        draft_state: DraftState,
-    }
-    let update = SessionUpdateData {
-        draft_state: DraftState::InProgress,
-    };
+    host: SessionHost,
+    db: &State<Store>,
+    hub: &State<EventHub>,
+) -> ApiResult<String> {
+    let _id = id;
+    run_action(db, hub, &host.session_id, host.session, Actor::Host, DraftAction::Start).await?;
 
-    let _updated: Option<Record> = db
-        .update((DRAFT_SESSION, id))
-        .merge(update)
-        .await
-        .map_err(|e| NotFound(e.to_string()))?;
     Ok(to_json_msg("All Good"))
 }
 
+// Preflight for the host routes below
+#[options("/draft_session/<id>/<action>", rank = 2)]
+pub fn option_host_action<'a>(id: &str, action: &str) -> &'a str {
+    let _ids = (id, action);
+    "Ok"
+}
+
+#[post("/draft_session/<id>/pause")]
+pub async fn pause(id: &str, host: SessionHost, db: &State<Store>, hub: &State<EventHub>) -> ApiResult<String> {
+    let _id = id;
+    run_action(db, hub, &host.session_id, host.session, Actor::Host, DraftAction::Pause).await?;
+
+    Ok(to_json_msg("Draft paused"))
+}
+
+#[post("/draft_session/<id>/resume")]
+pub async fn resume(id: &str, host: SessionHost, db: &State<Store>, hub: &State<EventHub>) -> ApiResult<String> {
+    let _id = id;
+    run_action(db, hub, &host.session_id, host.session, Actor::Host, DraftAction::Resume).await?;
+
+    Ok(to_json_msg("Draft resumed"))
+}
+
+#[post("/draft_session/<id>/cancel")]
+pub async fn cancel(id: &str, host: SessionHost, db: &State<Store>, hub: &State<EventHub>) -> ApiResult<String> {
+    let _id = id;
+    run_action(db, hub, &host.session_id, host.session, Actor::Host, DraftAction::Cancel).await?;
+
+    Ok(to_json_msg("Draft cancelled"))
+}
+
+/// Stops new players from joining. Sessions lock themselves once they're full.
+#[post("/draft_session/<id>/lock")]
+pub async fn lock(id: &str, host: SessionHost, db: &State<Store>, hub: &State<EventHub>) -> ApiResult<String> {
+    let _id = id;
+    run_action(db, hub, &host.session_id, host.session, Actor::Host, DraftAction::SetAcceptingPlayers { accepting: false }).await?;
+
+    Ok(to_json_msg("Session locked"))
+}
+
+#[post("/draft_session/<id>/unlock")]
+pub async fn unlock(id: &str, host: SessionHost, db: &State<Store>, hub: &State<EventHub>) -> ApiResult<String> {
+    let _id = id;
+    run_action(db, hub, &host.session_id, host.session, Actor::Host, DraftAction::SetAcceptingPlayers { accepting: true }).await?;
+
+    Ok(to_json_msg("Session unlocked"))
+}
+
+#[options("/draft_session/<id>/players/<user>")]
+pub fn option_player<'a>(id: &str, user: &str) -> &'a str {
+    let _ids = (id, user);
+    "Ok"
+}
+
+/// Removes a player before the draft starts. A session that locked itself when
+/// it filled up stays locked until the host unlocks it.
+#[delete("/draft_session/<id>/players/<user>")]
+pub async fn kick(
+    id: &str,
+    user: &str,
+    host: SessionHost,
+    db: &State<Store>,
+    hub: &State<EventHub>,
+) -> ApiResult<String> {
+    let _id = id;
+    let user_id = RecordId::from_table_key(DRAFT_USER_TB, user);
+    run_action(db, hub, &host.session_id, host.session, Actor::Host, DraftAction::Kick { user_id }).await?;
+
+    Ok(to_json_msg("Player removed"))
+}
+
+#[post(
+    "/draft_session/<id>/order",
+    format = "application/json",
+    data = "<order_form>"
+)]
+pub async fn reorder(
+    id: &str,
+    order_form: Json<PlayerOrderForm>,
+    host: SessionHost,
+    db: &State<Store>,
+    hub: &State<EventHub>,
+) -> ApiResult<Json<UpdateDraftSessionResponse>> {
+    let _id = id;
+    let order = order_form.0.players
+        .into_iter()
+        .map(|key| RecordId::from_table_key(DRAFT_USER_TB, key))
+        .collect();
+    let session = run_action(db, hub, &host.session_id, host.session, Actor::Host, DraftAction::Reorder { order }).await?;
+
+    Ok(Json(UpdateDraftSessionResponse::from(session)))
+}
+
+/// Takes back the latest pick or ban and hands the turn back to whoever made it.
+#[post("/draft_session/<id>/undo")]
+pub async fn undo(
+    id: &str,
+    host: SessionHost,
+    db: &State<Store>,
+    hub: &State<EventHub>,
+) -> ApiResult<Json<UpdateDraftSessionResponse>> {
+    let _id = id;
+    let session = run_action(db, hub, &host.session_id, host.session, Actor::Host, DraftAction::Undo).await?;
+
+    Ok(Json(UpdateDraftSessionResponse::from(session)))
+}
+
 #[get("/draft_session/<id>/update")]
 pub async fn update_draft_session(
     id: &str,
-    db: &State<Surreal<Client>>,
-) -> Result<Json<UpdateDraftSessionResponse>, NotFound<String>> {
-    let query =
-        format!("SELECT *,(SELECT * from ->{DRAFT_USER_RELATION}.out ORDER BY order_in_session ASC) as players FROM draft_session:{id};");
-
-    let session: DraftSession = match run_query(query, db).await {
-        Some(s) => s,
-        None => return Err(NotFound("Session not found".into())),
-    };
+    db: &State<Store>,
+) -> ApiResult<Json<UpdateDraftSessionResponse>> {
+    let session = find_session(id, db).await?;
 
     let resp = UpdateDraftSessionResponse::from(session);
     Ok(Json(resp))
 }
 
+/// Everything that happened in the session, oldest first.
+#[get("/draft_session/<id>/history")]
+pub async fn get_history(
+    id: &str,
+    db: &State<Store>,
+) -> ApiResult<Json<Vec<DraftEventRecord>>> {
+    find_session(id, db).await?;
+
+    Ok(Json(db.list_draft_events(id).await?))
+}
+
+/// The session rebuilt from its history, as it was right before `turn` was
+/// played. Without a `turn` it should match the session as it is now.
+#[get("/draft_session/<id>/replay?<turn>")]
+pub async fn replay(
+    id: &str,
+    turn: Option<u32>,
+    db: &State<Store>,
+    pokedex: &State<Arc<Pokedex>>,
+) -> ApiResult<Json<DraftSession>> {
+    let mut session = find_session(id, db).await?;
+    store::load_draft_pool(db, &mut session).await?;
+    session.pokedex = pokedex.inner().clone();
+    let records = db.list_draft_events(id).await?;
+
+    // a history the engine turns away no longer fits the session, it isn't a server fault
+    match history::replay(&session, &records, turn) {
+        Ok(replayed) => Ok(Json(hide_secrets(replayed))),
+        Err(e) => Err(ApiError::conflict("replay_failed", format!("Unable to replay the session: {e}"))),
+    }
+}
+
+#[get("/draft_session/<id>/events")]
+pub async fn draft_session_events(
+    id: &str,
+    db: &State<Store>,
+    hub: &State<EventHub>,
+    mut end: Shutdown,
+) -> ApiResult<EventStream![]> {
+    find_session(id, db).await?;
+    let mut rx = hub.subscribe(id);
+    let (db, id) = (db.inner().clone(), id.to_string());
+
+    Ok(EventStream! {
+        // `action_count` of the last resync, events up to it are already in there
+        let mut seen = None;
+
+        loop {
+            let msg = select! {
+                msg = rx.recv() => msg,
+                _ = &mut end => break,
+            };
+
+            match msg {
+                Ok(event) if seen.is_some_and(|count| event.seen_at(count)) => continue,
+                Ok(event) => {
+                    let mut sse = Event::json(&event.event).event(event.event.name());
+                    if let Some(seq) = event.seq {
+                        sse = sse.id(seq.to_string());
+                    }
+                    yield sse;
+                },
+                Err(RecvError::Closed) => break,
+                // events were dropped, hand over the whole state instead
+                Err(RecvError::Lagged(_)) => match db.get_session(&id).await {
+                    Ok(Some(session)) => {
+                        seen = Some(session.action_count);
+                        yield Event::json(&UpdateDraftSessionResponse::from(session)).event("resync");
+                    },
+                    _ => break,
+                },
+            }
+        }
+    })
+}
+
 // TODO: Change this to `join`
 #[options("/draft_session/<id>/create-user")]
 pub fn option_create_user<'a>(id: &str) -> &'a str {
@@ -231,94 +415,45 @@ pub fn option_create_user<'a>(id: &str) -> &'a str {
 pub async fn create_user(
     user_form: Json<DraftUserForm>,
     id: &str,
-    db: &State<Surreal<Client>>,
-) -> Result<Json<DraftUserReturnData>, NotFound<String>> {
+    db: &State<Store>,
+    hub: &State<EventHub>,
+) -> ApiResult<Json<DraftUserReturnData>> {
     let new_username = user_form.0.name;
-    let query =
-        format!("SELECT *,->{DRAFT_USER_RELATION}.out.* as players FROM draft_session:{id};");
-
-    // Guarding Checks
-    let session: DraftSession = match run_query(query, db).await {
-        Some(s) => s,
-        None => return Err(NotFound(to_json_msg("Session not found"))),
-    };
-
-    if session.num_of_players() >= (session.max_num_players as u32)
-        && (session.draft_state != DraftState::InProgress
-            || session.draft_state != DraftState::Ended)
-    {
-        return Err(NotFound(to_json_msg(
-            "Draft is no longer accepting players.",
-        )));
-    }
 
-    if session.is_name_taken(&new_username) {
-        return Err(NotFound(to_json_msg("Username already in use")));
-    }
-
-    if !session.slots_available() {
-        return Err(NotFound(to_json_msg("No slots available to join")));
-    }
+    let session = find_session(id, db).await?;
 
     // Create User
     let key = Uuid::new_v4();
-    let hash = hash_uuid(&key);
-
-    let new_user = DraftUser::new(new_username.clone(), hash, session.num_of_players());
-    let new_record: DraftUser = match db.create("draft_user").content(new_user).await {
-        Ok(Some(r)) => r,
-        Ok(None) => return Err(NotFound(to_json_msg("Could not create record"))),
-        Err(e) => {
-            println!("{}", e);
-            return Err(NotFound(to_json_msg("Could not create record")));
-        }
+    let hash = KeyHash::new(&key);
+    let new_user_key = Uuid::new_v4().simple().to_string();
+    let new_user_id = RecordId::from_table_key(DRAFT_USER_TB, new_user_key.as_str());
+    let mut new_user = DraftUser::new(new_username.clone(), hash, session.num_of_players());
+    new_user.id = Some(new_user_id.clone());
+
+    // the user only gets stored once the engine has let them in
+    let (turn, action_count, now) = (session.turn_ticker, session.action_count, now_ms());
+    let action = DraftAction::Join { user: new_user };
+    let (session, events) = engine::apply(session, action.clone(), now)?;
+    let new_record = match session.get_player(&new_user_id) {
+        Some(u) => u.clone(),
+        None => return Err(ApiError::internal("Could not create record")),
     };
-
-    let record_id = RecordId::from_table_key("draft_session", id);
-    let new_user_id = new_record.id.unwrap();
-
-    relate_objects(
-        db,
-        &record_id,
-        &new_user_id,
-        DRAFT_USER_RELATION,
-    )
-    .await?;
-
-    #[derive(Serialize)]
-    struct UpdateData {
-        accepting_players: bool,
-        draft_state: DraftState,
-        #[serde(skip_serializing_if = "Option::is_none")]
-        current_player: Option<RecordId>,
+    if !db.update_session_if(&session, action_count).await? {
+        return Err(session_changed());
     }
-    let mut update_data = UpdateData {
-        accepting_players: true,
-        current_player: None,
-        draft_state: DraftState::Open,
-    };
-
-    if session.num_of_players() == 0 {
-        update_data.current_player = Some(new_user_id.clone());
-    };
-
-    // TODO: Make everything a u32
-    if session.num_of_players() + 1 >= (session.max_num_players as u32) {
-        update_data.accepting_players = false;
-    };
-
-    let _updated: Option<Record> = db
-        .update((DRAFT_SESSION, id))
-        .merge(update_data)
-        .await
-        .map_err(|e| NotFound(e.to_string()))?;
+    db.create_user(id, new_record).await?;
+    let actor = Actor::Player { name: new_username.clone() };
+    if let Some(record) = DraftEventRecord::new(turn, &session, &action, actor, now, &events) {
+        db.add_draft_event(record).await?;
+    }
+    hub.publish(id, Some(session.action_count), events);
 
     let return_data = DraftUserReturnData::new(
         new_username.clone(),
         id.into(),
         new_user_id,
         false,
-        format!("{key}"),
+        player_token(&new_user_key, &key),
     );
 
     Ok(Json(return_data))
@@ -335,186 +470,157 @@ pub fn option_select_pokemon<'a>(id: &str) -> &'a str {
     format = "application/json",
     data = "<select_pokemon_form>"
 )]
-pub async fn select_pokemon<'a>(
+pub async fn select_pokemon(
     select_pokemon_form: Json<SelectPokemonRequest>,
     id: &str,
-    db: &State<Surreal<Client>>,
-) -> Result<Json<SelectPokemonResponse>, NotFound<String>> {
-    let select_pokemon = select_pokemon_form.0;
-
-    let query =
-        format!("SELECT *,(SELECT * from ->{DRAFT_USER_RELATION}.out ORDER BY order_in_session ASC) as players FROM draft_session:{id};");
-
-    let session: DraftSession = match run_query(query, db).await {
-        Some(s) => s,
-        None => return Err(NotFound("Session not found".into())),
-    };
-
-    let draft_user_id = select_pokemon.user_id;
-
-    let key_hash = match Uuid::parse_str(&select_pokemon.secret) {
-        Ok(k) => hash_uuid(&k),
-        Err(_) => return Err(NotFound("Could not parse uuid".into())),
-    };
-
-    if session.draft_state != DraftState::InProgress {
-        return Err(NotFound(to_json_msg("Draft has not yet started")));
-    }
-    if session.is_pokemon_chosen(&select_pokemon.pokemon_id) {
-        return Err(NotFound(to_json_msg(
-            "Pokemon cannot be selected. It's either banned or has already been selected.",
-        )));
-    }
-    if select_pokemon.action != session.current_phase {
-        return Err(NotFound(to_json_msg("Current action not allowed")));
-    }
-    if !session.is_current_player(&draft_user_id) {
-        return Err(NotFound(to_json_msg("It is not your turn")));
-    };
-
-    // Get Next Player ID in session
-    let (turn, next_player_id) = session.get_next_player_id();
-
-    // Get Next Phase in Session
-    let next_phase = session.get_next_phase();
-    // Check if Session has ended
-    let draft_state = if session.check_if_session_is_over() {
-        DraftState::Ended
-    } else {
-        session.draft_state
-    };
+    player: AuthenticatedPlayer,
+    db: &State<Store>,
+    hub: &State<EventHub>,
+    pokedex: &State<Arc<Pokedex>>,
+) -> ApiResult<Json<SelectPokemonResponse>> {
+    let _id = id;
+    let resp = apply_selection(db, hub, pokedex, player, select_pokemon_form.0).await?;
 
-    let (mut pokemon_chosen_in_session, players) = (session.selected_pokemon, session.players);
-    let players = match players {
-        Some(p) => p,
-        None => return Err(NotFound(to_json_msg("Nothing"))),
-    };
-    let mut player = match get_current_player(players, &draft_user_id) {
-        Some(p) => p,
-        None => return Err(NotFound(to_json_msg("User not in session."))),
-    };
+    Ok(Json(resp))
+}
 
-    if !player.check_key_hash(key_hash) {
-        return Err(NotFound(to_json_msg("Access Denied")));
+/// Picks or bans for `player`. Shared by the HTTP route and the draft room socket.
+pub(crate) async fn apply_selection(
+    db: &Store,
+    hub: &EventHub,
+    pokedex: &Arc<Pokedex>,
+    player: AuthenticatedPlayer,
+    select_pokemon: SelectPokemonRequest,
+) -> ApiResult<SelectPokemonResponse> {
+    let draft_user_id = player.user_id;
+    let mut session = player.session;
+    store::load_draft_pool(db, &mut session).await?;
+    session.pokedex = pokedex.clone();
+
+    let action = DraftAction::Select {
+        user_id: draft_user_id.clone(),
+        pokemon_id: select_pokemon.pokemon_id,
+        action: select_pokemon.action,
     };
-
-    if let DraftPhase::Pick = select_pokemon.action {
-        player.selected_pokemon.push(select_pokemon.pokemon_id);
+    let actor = Actor::Player { name: player.player.name };
+    let session = run_action(db, hub, &player.session_id, session, actor, action).await?;
+    let player = match session.get_player(&draft_user_id) {
+        Some(p) => p.clone(),
+        None => return Err(ApiError::not_found("user_not_found", "User not in session.")),
     };
 
-    pokemon_chosen_in_session.push(select_pokemon.pokemon_id);
-
-    // update Session
-    #[derive(Serialize)]
-    struct SessionUpdateData {
-        selected_pokemon: Vec<u32>,
-        turn_ticker: u32,
-        current_player: Option<RecordId>,
-        current_phase: DraftPhase,
-        draft_state: DraftState,
-    }
-    let update_data = SessionUpdateData {
-        selected_pokemon: pokemon_chosen_in_session.clone(),
-        turn_ticker: turn,
-        current_player: next_player_id,
-        current_phase: next_phase,
-        draft_state,
-    };
-    let _updated: Option<Record> = db
-        .update((DRAFT_SESSION, id))
-        .merge(update_data)
-        .await
-        .map_err(|e| NotFound(e.to_string()))?;
-
-    #[derive(Serialize)]
-    struct PlayerUpdateData {
-        selected_pokemon: Vec<u32>,
-    }
-    let update_data = PlayerUpdateData {
-        selected_pokemon: player.selected_pokemon.clone(),
-    };
-    let _updated: Option<Record> = db
-        .update(draft_user_id)
-        .merge(update_data)
-        .await
-        .map_err(|e| NotFound(e.to_string()))?;
-
-    // TODO selected_pokemon should be set to the updated array of pk_ids
-    Ok(Json(SelectPokemonResponse {
+    Ok(SelectPokemonResponse {
+        points_remaining: session.points_remaining(&draft_user_id),
         selected_pokemon: player.selected_pokemon,
-        banned_pokemon: pokemon_chosen_in_session,
-        phase: next_phase,
-    }))
+        picked: SelectionData::list(&session, &session.picked),
+        banned: SelectionData::list(&session, &session.banned),
+        phase: session.current_phase,
+    })
 }
 
+// structs
+#[derive(Debug, Serialize)]
+pub struct CreateDraftSessionResponse {
+    #[serde(flatten)]
+    session: DraftSession,
+    /// Goes in `Authorization: Bearer <token>` for the host-only routes.
+    host_token: String,
+}
 
-fn get_current_player(players: Vec<DraftUser>, id: &RecordId) -> Option<DraftUser> {
-    for player in players {
-        if let Some(ref t) = player.id {
-            if t == id {
-                return Some(player);
-            }
-        }
-    }
-    None
+/// `draft_user` keys of every player in the session, first to last.
+#[derive(Debug, Deserialize)]
+pub struct PlayerOrderForm {
+    players: Vec<String>,
 }
 
-// structs
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SelectPokemonRequest {
-    user_id: RecordId,
-    pokemon_id: u32,
-    action: DraftPhase,
-    secret: String,
+    pub pokemon_id: u32,
+    pub action: DraftPhase,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SelectPokemonResponse {
+    /// The player's own picks.
     selected_pokemon: Vec<u32>,
-    banned_pokemon: Vec<u32>,
+    /// What the player has left to spend, only with a point budget.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    points_remaining: Option<u32>,
+    picked: Vec<SelectionData>,
+    banned: Vec<SelectionData>,
     phase: DraftPhase,
 }
 
+/// A pick or ban and the name of the player who made it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectionData {
+    pokemon_id: u32,
+    player: Option<String>,
+}
+
+impl SelectionData {
+    fn list(session: &DraftSession, selections: &[Selection]) -> Vec<SelectionData> {
+        selections
+            .iter()
+            .map(|s| SelectionData {
+                pokemon_id: s.pokemon_id,
+                player: session.get_player(&s.user_id).map(|p| p.name.clone()),
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateDraftSessionResponse {
     current_phase: DraftPhase,
-    banned_pokemon: Vec<u32>,
+    picked: Vec<SelectionData>,
+    banned: Vec<SelectionData>,
     current_player: Option<String>,
     players: Vec<PlayerData>,
     state: DraftState,
+    /// Unix milliseconds when the current turn runs out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    turn_deadline: Option<u64>,
+    /// Events with a `seq` up to this one are already part of the snapshot.
+    pub action_count: u32,
 }
 
 impl UpdateDraftSessionResponse {
-    fn from(session: DraftSession) -> UpdateDraftSessionResponse {
+    pub fn from(session: DraftSession) -> UpdateDraftSessionResponse {
         let current_player_name = session.get_current_player_name();
-        let (selected_pokemon, current_phase, players) = (
-            session.selected_pokemon,
-            session.current_phase,
-            session.players,
-        );
-        let players: Vec<DraftUser> = match players {
-            Some(p) => p,
-            None => Vec::new(),
-        };
+        let points: Vec<Option<u32>> = session.players
+            .iter()
+            .flatten()
+            .map(|p| p.id.as_ref().and_then(|id| session.points_remaining(id)))
+            .collect();
+        let picked = SelectionData::list(&session, &session.picked);
+        let banned = SelectionData::list(&session, &session.banned);
+        let (current_phase, players) = (session.current_phase, session.players);
+        let players: Vec<DraftUser> = players.unwrap_or_default();
 
         // TODO clone is very expensive, figure out a way to avoid using it
         // TODO get as slice maybe?
         // Oh it gets data from players so probably can't use slices
         let player_data: Vec<PlayerData> = players
             .iter()
-            .map(|element| PlayerData {
+            .zip(points)
+            .map(|(element, points_remaining)| PlayerData {
                 name: element.name.clone(),
                 pokemon: element.selected_pokemon.clone(),
                 ready: element.ready,
+                points_remaining,
             })
             .collect();
 
         UpdateDraftSessionResponse {
-            banned_pokemon: selected_pokemon,
-            current_phase: current_phase,
+            picked,
+            banned,
+            current_phase,
             current_player: current_player_name,
             players: player_data,
             state: session.draft_state,
+            turn_deadline: session.turn_deadline,
+            action_count: session.action_count,
         }
     }
 }
@@ -524,18 +630,6 @@ pub struct PlayerData {
     name: String,
     pokemon: Vec<u32>,
     ready: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    points_remaining: Option<u32>,
 }
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PokemonSubData<'a> {
-    name: &'a str,
-    type1: PokemonType,
-    type2: PokemonType,
-    id: u32,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ReadyDraftUserForm {
-    user_id: String,
-}
-
@@ -1,41 +1,174 @@
-use crate::api::utils::run_query;
-use crate::models::pokemon::PokemonDraftSet;
+use std::sync::Arc;
+
+use crate::api::auth::DraftSetOwner;
+use crate::api::error::{ApiError, ApiResult};
+use crate::models::key::KeyHash;
+use crate::models::pokedex::Pokedex;
+use crate::models::pokemon::{DraftSetMember, PokemonDraftSet, PokemonFilter};
+use crate::store::Store;
 
 use rocket::State;
-use rocket::serde::json::Json;
+use rocket::serde::json::{json, Json};
+
+use serde::{Deserialize, Serialize};
+
+use uuid::Uuid;
+
+pub(crate) fn set_not_found(id: &str) -> ApiError {
+    ApiError::not_found("draft_set_not_found", format!("No draft set with id {id}"))
+}
+
+/// The set's pokemon: everything `filter` picks out, then `pokemon` on top.
+/// Entries in `pokemon` carry points and tiers, filtered ones don't.
+#[derive(Debug, Deserialize)]
+pub struct DraftSetForm {
+    name: String,
+    #[serde(default)]
+    pokemon: Vec<DraftSetMember>,
+    #[serde(default)]
+    filter: Option<PokemonFilter>,
+}
+
+/// Every field is optional. `add` replaces the points and tier of members
+/// already in the set.
+#[derive(Debug, Deserialize)]
+pub struct DraftSetUpdateForm {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    add: Vec<DraftSetMember>,
+    #[serde(default)]
+    add_filter: Option<PokemonFilter>,
+    #[serde(default)]
+    remove: Vec<u32>,
+}
 
-use surrealdb::Surreal;
-use surrealdb::engine::remote::ws::Client;
+#[derive(Debug, Serialize)]
+pub struct CreateDraftSetResponse {
+    #[serde(flatten)]
+    set: PokemonDraftSet,
+    /// Goes in `Authorization: Bearer <token>` to change or delete the set.
+    owner_token: String,
+}
+
+/// `filtered` as bare members followed by `listed`, one entry per pokemon.
+/// Anything not in the pokedex is turned away.
+fn resolve_members(
+    pokedex: &Pokedex,
+    filter: Option<&PokemonFilter>,
+    listed: Vec<DraftSetMember>,
+) -> ApiResult<Vec<DraftSetMember>> {
+    let unknown: Vec<u32> = listed
+        .iter()
+        .map(|m| m.dex_id)
+        .chain(filter.map(|f| f.include.clone()).unwrap_or_default())
+        .filter(|id| pokedex.get(*id).is_none())
+        .collect();
+    if !unknown.is_empty() {
+        return Err(ApiError::bad_request("pokemon_not_found", "Some of those pokemon don't exist")
+            .with_details(json!({ "pokemon": unknown })));
+    }
+
+    let mut members: Vec<DraftSetMember> = filter
+        .map(|f| f.select(pokedex.iter()))
+        .unwrap_or_default()
+        .into_iter()
+        .map(DraftSetMember::new)
+        .collect();
+    for member in listed {
+        members.retain(|m| m.dex_id != member.dex_id);
+        members.push(member);
+    }
+    Ok(members)
+}
+
+fn check_name(name: &str) -> ApiResult<()> {
+    if name.trim().is_empty() {
+        return Err(ApiError::bad_request("invalid_name", "A draft set needs a name"));
+    }
+    Ok(())
+}
 
 #[get("/draft_set")]
-pub async fn list_pokemon_draft_set(db: &State<Surreal<Client>>) -> Json<Vec<PokemonDraftSet>> {
-    let draft_sets = match db.select("pokemon_draft_set").await {
-        Ok(p) => p,
-        Err(e) => {
-            println!("{}", e);
-            Vec::new()
-        }
-    };
+pub async fn list_pokemon_draft_set(db: &State<Store>) -> ApiResult<Json<Vec<PokemonDraftSet>>> {
+    let draft_sets = db.list_draft_sets().await?;
 
-    Json(draft_sets)
+    Ok(Json(draft_sets))
 }
 
 #[get("/draft_set/<id>?<detailed>")]
 pub async fn get_pokemon_draft_set(
     id: &str,
     detailed: bool,
-    db: &State<Surreal<Client>>,
-) -> Option<Json<PokemonDraftSet>> {
-    let query: String = if !detailed {
-        format!("SELECT name,id,array::sort(->contains.out.dex_id, asc) as pokemon.Ids FROM pokemon_draft_set:{id};")
+    db: &State<Store>,
+) -> ApiResult<Json<PokemonDraftSet>> {
+    match db.get_draft_set(id, detailed).await? {
+        Some(p) => Ok(Json(p)),
+        None => Err(set_not_found(id)),
+    }
+}
+
+#[options("/draft_set/<id>")]
+pub fn option_draft_set<'a>(id: &str) -> &'a str {
+    let _id = id;
+    "Ok"
+}
+
+#[post("/draft_set/create", format = "application/json", data = "<set_form>")]
+pub async fn create_draft_set(
+    set_form: Json<DraftSetForm>,
+    db: &State<Store>,
+    pokedex: &State<Arc<Pokedex>>,
+) -> ApiResult<Json<CreateDraftSetResponse>> {
+    let set_form = set_form.0;
+    check_name(&set_form.name)?;
+
+    let members = resolve_members(pokedex, set_form.filter.as_ref(), set_form.pokemon)?;
+
+    let owner_key = Uuid::new_v4();
+    let set = db.create_draft_set(&set_form.name, members, KeyHash::new(&owner_key)).await?;
+    Ok(Json(CreateDraftSetResponse { set, owner_token: owner_key.to_string() }))
+}
+
+#[patch("/draft_set/<_id>", format = "application/json", data = "<update_form>")]
+pub async fn update_draft_set(
+    _id: &str,
+    update_form: Json<DraftSetUpdateForm>,
+    owner: DraftSetOwner,
+    db: &State<Store>,
+    pokedex: &State<Arc<Pokedex>>,
+) -> ApiResult<Json<PokemonDraftSet>> {
+    let update_form = update_form.0;
+
+    // check everything before writing anything, a bad request changes nothing
+    if let Some(name) = &update_form.name {
+        check_name(name)?;
+    }
+    let added = if !update_form.add.is_empty() || update_form.add_filter.is_some() {
+        resolve_members(pokedex, update_form.add_filter.as_ref(), update_form.add)?
     } else {
-        format!("SELECT name,id,array::sort(->contains.out.*, asc) as pokemon.Stats FROM pokemon_draft_set:{id};")
+        vec![]
     };
 
-    match run_query(query, db).await {
-        Some(p) => Some(Json(p)),
-        None => None,
+    if let Some(name) = &update_form.name {
+        db.rename_draft_set(&owner.set_id, name).await?;
+    }
+    if !update_form.remove.is_empty() {
+        db.remove_draft_set_members(&owner.set_id, update_form.remove).await?;
+    }
+    if !added.is_empty() {
+        db.add_draft_set_members(&owner.set_id, added).await?;
+    }
+
+    match db.get_draft_set(&owner.set_id, false).await? {
+        Some(set) => Ok(Json(set)),
+        None => Err(set_not_found(&owner.set_id)),
     }
 }
 
+#[delete("/draft_set/<_id>")]
+pub async fn delete_draft_set(_id: &str, owner: DraftSetOwner, db: &State<Store>) -> ApiResult<String> {
+    db.delete_draft_set(&owner.set_id).await?;
 
+    Ok("{\"message\": \"Draft set deleted\"}".to_string())
+}
@@ -0,0 +1,209 @@
+use crate::models::draft::engine::RuleViolation;
+use crate::models::draft::roster::RosterViolation;
+use crate::store::StoreError;
+
+use rocket::http::Status;
+use rocket::request::Outcome;
+use rocket::response::{self, Responder};
+use rocket::serde::json::{json, Json, Value};
+use rocket::{Catcher, Request};
+
+use serde::Serialize;
+
+/// Body sent back with every failed request. `code` is stable and meant to be
+/// matched on by clients, `message` is for people.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorBody {
+    pub code: &'static str,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+#[derive(Debug, Clone)]
+pub enum ApiError {
+    BadRequest(ErrorBody),
+    Unauthorized(ErrorBody),
+    Forbidden(ErrorBody),
+    NotFound(ErrorBody),
+    Conflict(ErrorBody),
+    Internal(ErrorBody),
+}
+
+fn body(code: &'static str, message: impl Into<String>) -> ErrorBody {
+    ErrorBody {
+        code,
+        message: message.into(),
+        details: None,
+    }
+}
+
+impl ApiError {
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> ApiError {
+        ApiError::BadRequest(body(code, message))
+    }
+
+    pub fn unauthorized(code: &'static str, message: impl Into<String>) -> ApiError {
+        ApiError::Unauthorized(body(code, message))
+    }
+
+    pub fn forbidden(code: &'static str, message: impl Into<String>) -> ApiError {
+        ApiError::Forbidden(body(code, message))
+    }
+
+    pub fn not_found(code: &'static str, message: impl Into<String>) -> ApiError {
+        ApiError::NotFound(body(code, message))
+    }
+
+    pub fn conflict(code: &'static str, message: impl Into<String>) -> ApiError {
+        ApiError::Conflict(body(code, message))
+    }
+
+    pub fn internal(message: impl Into<String>) -> ApiError {
+        ApiError::Internal(body("internal_error", message))
+    }
+
+    pub fn with_details(mut self, details: Value) -> ApiError {
+        self.body_mut().details = Some(details);
+        self
+    }
+
+    pub fn status(&self) -> Status {
+        match self {
+            ApiError::BadRequest(_) => Status::BadRequest,
+            ApiError::Unauthorized(_) => Status::Unauthorized,
+            ApiError::Forbidden(_) => Status::Forbidden,
+            ApiError::NotFound(_) => Status::NotFound,
+            ApiError::Conflict(_) => Status::Conflict,
+            ApiError::Internal(_) => Status::InternalServerError,
+        }
+    }
+
+    fn body_mut(&mut self) -> &mut ErrorBody {
+        match self {
+            ApiError::BadRequest(b)
+            | ApiError::Unauthorized(b)
+            | ApiError::Forbidden(b)
+            | ApiError::NotFound(b)
+            | ApiError::Conflict(b)
+            | ApiError::Internal(b) => b,
+        }
+    }
+
+    /// Fails a request guard with this error. Rocket only hands the status to
+    /// the catcher, so the error is kept on the request for it to send.
+    pub fn guard_failure<T>(self, req: &Request<'_>) -> Outcome<T, ApiError> {
+        let status = self.status();
+        req.local_cache(|| GuardFailure(Some(self.clone())));
+        Outcome::Error((status, self))
+    }
+
+    pub fn into_body(self) -> ErrorBody {
+        match self {
+            ApiError::BadRequest(b)
+            | ApiError::Unauthorized(b)
+            | ApiError::Forbidden(b)
+            | ApiError::NotFound(b)
+            | ApiError::Conflict(b)
+            | ApiError::Internal(b) => b,
+        }
+    }
+}
+
+impl From<StoreError> for ApiError {
+    fn from(e: StoreError) -> ApiError {
+        // the database error itself stays in the logs
+        error!("{}", e);
+        ApiError::internal("Unable to reach the draft store")
+    }
+}
+
+impl From<RuleViolation> for ApiError {
+    fn from(v: RuleViolation) -> ApiError {
+        let message = v.to_string();
+        match v {
+            RuleViolation::NotAcceptingPlayers => ApiError::conflict("not_accepting_players", message),
+            RuleViolation::NameTaken => ApiError::conflict("name_taken", message),
+            RuleViolation::SessionFull => ApiError::conflict("session_full", message),
+            RuleViolation::UserNotInSession => ApiError::not_found("user_not_found", message),
+            RuleViolation::NoPlayers => ApiError::conflict("no_players", message),
+            RuleViolation::DraftInProgress => ApiError::conflict("draft_in_progress", message),
+            RuleViolation::DraftNotStarted => ApiError::conflict("draft_not_started", message),
+            RuleViolation::PokemonUnavailable => ApiError::conflict("pokemon_unavailable", message),
+            RuleViolation::ActionNotAllowed { current_phase } => ApiError::conflict("action_not_allowed", message)
+                .with_details(json!({ "current_phase": current_phase })),
+            RuleViolation::NotYourTurn => ApiError::forbidden("not_your_turn", message),
+            RuleViolation::DraftPaused => ApiError::conflict("draft_paused", message),
+            RuleViolation::DraftOver => ApiError::conflict("draft_over", message),
+            RuleViolation::DraftAlreadyStarted => ApiError::conflict("draft_already_started", message),
+            RuleViolation::InvalidTransition { from, to } => ApiError::conflict("invalid_transition", message)
+                .with_details(json!({ "from": from, "to": to })),
+            RuleViolation::InvalidOrder => ApiError::bad_request("invalid_order", message),
+            RuleViolation::NotEnoughPlayers { min, joined } => ApiError::conflict("not_enough_players", message)
+                .with_details(json!({ "min": min, "joined": joined })),
+            RuleViolation::PlayersNotReady => ApiError::conflict("players_not_ready", message),
+            RuleViolation::TurnNotOver => ApiError::conflict("turn_not_over", message),
+            RuleViolation::NotInDraftSet => ApiError::bad_request("pokemon_not_in_draft_set", message),
+            RuleViolation::NothingToUndo => ApiError::conflict("nothing_to_undo", message),
+            RuleViolation::InvalidTurnOrder => ApiError::conflict("invalid_turn_order", message),
+            RuleViolation::OverBudget { cost, remaining } => ApiError::conflict("over_budget", message)
+                .with_details(json!({ "cost": cost, "remaining": remaining })),
+            RuleViolation::TierFull { tier, max_picks } => ApiError::conflict("tier_full", message)
+                .with_details(json!({ "tier": tier, "max_picks": max_picks })),
+            RuleViolation::RosterConstraint(v) => ApiError::conflict("roster_constraint", message)
+                .with_details(roster_details(&v)),
+        }
+    }
+}
+
+/// Names the constraint that failed, along with what it was set to.
+fn roster_details(v: &RosterViolation) -> Value {
+    let constraint = v.constraint();
+    match v {
+        RosterViolation::TooManyLegendaries { max } | RosterViolation::TooManyMythics { max } => {
+            json!({ "constraint": constraint, "max": max })
+        },
+        RosterViolation::TooManyOfType { pokemon_type, max } => {
+            json!({ "constraint": constraint, "type": pokemon_type, "max": max })
+        },
+        RosterViolation::TypesNotCovered { missing } => json!({ "constraint": constraint, "missing": missing }),
+        RosterViolation::EvolutionLineTaken { pokemon_id } => {
+            json!({ "constraint": constraint, "pokemon_id": pokemon_id })
+        },
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let status = self.status();
+        (status, Json(self.into_body())).respond_to(req)
+    }
+}
+
+pub type ApiResult<T> = Result<T, ApiError>;
+
+struct GuardFailure(Option<ApiError>);
+
+// Rocket's own failures (unknown routes, bodies that don't parse, ...) get the same shape
+#[catch(default)]
+fn default_catcher(status: Status, req: &Request) -> (Status, Json<ErrorBody>) {
+    if let GuardFailure(Some(e)) = req.local_cache(|| GuardFailure(None)) {
+        return (e.status(), Json(e.clone().into_body()));
+    }
+
+    let code = match status.code {
+        400 => "bad_request",
+        401 => "unauthorized",
+        403 => "forbidden",
+        404 => "not_found",
+        409 => "conflict",
+        422 => "unprocessable_entity",
+        _ => "internal_error",
+    };
+    let message = status.reason().unwrap_or("Unknown error");
+
+    (status, Json(body(code, message)))
+}
+
+pub fn catchers() -> Vec<Catcher> {
+    catchers![default_catcher]
+}
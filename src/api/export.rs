@@ -0,0 +1,61 @@
+//! Drafted rosters as text other tools import, only Showdown's team format so
+//! far. Like coverage, anyone with the session id can export.
+use std::sync::Arc;
+
+use crate::api::draft_session::{find_player, find_session};
+use crate::api::error::{ApiError, ApiResult};
+use crate::models::pokedex::Pokedex;
+use crate::models::showdown;
+use crate::store::Store;
+
+use rocket::State;
+use rocket::serde::json::json;
+
+const FORMATS: [&str; 1] = ["showdown"];
+
+/// Showdown when left out.
+fn check_format(format: Option<&str>) -> ApiResult<()> {
+    match format {
+        None | Some("showdown") => Ok(()),
+        Some(other) => Err(ApiError::bad_request("unsupported_format", format!("Can't export as {other}"))
+            .with_details(json!({ "formats": FORMATS }))),
+    }
+}
+
+/// One player's roster as a team, in pick order.
+#[get("/draft_session/<id>/players/<user>/export?<format>")]
+pub async fn export_player(
+    id: &str,
+    user: &str,
+    format: Option<&str>,
+    db: &State<Store>,
+    pokedex: &State<Arc<Pokedex>>,
+) -> ApiResult<String> {
+    check_format(format)?;
+    let session = find_session(id, db).await?;
+    let player = find_player(&session, user)?;
+
+    Ok(showdown::team(&pokedex.roster(&player.selected_pokemon)))
+}
+
+/// Every player's roster, each under the player's name, in turn order.
+#[get("/draft_session/<id>/export?<format>")]
+pub async fn export_league(
+    id: &str,
+    format: Option<&str>,
+    db: &State<Store>,
+    pokedex: &State<Arc<Pokedex>>,
+) -> ApiResult<String> {
+    check_format(format)?;
+    let session = find_session(id, db).await?;
+    let players = session.players.unwrap_or_default();
+
+    let teams: Vec<(String, _)> = players
+        .into_iter()
+        .map(|p| {
+            let roster = pokedex.roster(&p.selected_pokemon);
+            (p.name, roster)
+        })
+        .collect();
+    Ok(showdown::league(&teams))
+}
@@ -2,12 +2,18 @@ use rocket::http::Header;
 use rocket::{Request, Response};
 use rocket::fairing::{Fairing, Info, Kind};
 
+pub mod auth;
 pub mod pokemon;
 pub mod draft_set;
 pub mod draft_rules;
 pub mod draft_session;
-mod utils;
+pub mod draft_room;
+pub mod pick_queue;
+pub mod coverage;
+pub mod export;
+pub mod error;
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct CORS;
 
 #[rocket::async_trait]
@@ -23,9 +29,10 @@ impl Fairing for CORS {
         response.set_header(Header::new("Access-Control-Allow-Origin", "*"));
         response.set_header(Header::new(
             "Access-Control-Allow-Methods",
-            "POST, GET, PATCH, OPTIONS",
+            "POST, GET, PATCH, DELETE, OPTIONS",
         ));
-        response.set_header(Header::new("Access-Control-Allow-Headers", "*"));
+        // the wildcard doesn't cover Authorization
+        response.set_header(Header::new("Access-Control-Allow-Headers", "Authorization, *"));
         response.set_header(Header::new("Access-Control-Allow-Credentials", "true"));
     }
 }
@@ -0,0 +1,105 @@
+//! A player's pick queue: the pokemon they're after, best first. It's private to
+//! the player, nothing here goes out over the event stream. Pokemon leave the
+//! queue on their own once someone picks or bans them, and a timed out pick
+//! takes the top of the queue. The queue is only ever written through
+//! `set_pick_queue`, so it can't undo or be undone by a pick landing at the
+//! same time.
+use crate::api::auth::AuthenticatedPlayer;
+use crate::api::error::{ApiError, ApiResult};
+use crate::store::{self, Store};
+
+use rocket::serde::json::Json;
+use rocket::State;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PickQueue {
+    pokemon: Vec<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MoveInQueueRequest {
+    pokemon_id: u32,
+    /// Zero based, past the end moves it to the back.
+    position: usize,
+}
+
+/// The queue as stored, less whatever was picked or banned since it was set.
+fn current_queue(player: &AuthenticatedPlayer) -> Vec<u32> {
+    player.player.pick_queue()
+        .iter()
+        .copied()
+        .filter(|pk| !player.session.is_pokemon_chosen(pk))
+        .collect()
+}
+
+fn queue_of(player: &AuthenticatedPlayer) -> Json<PickQueue> {
+    Json(PickQueue { pokemon: current_queue(player) })
+}
+
+#[options("/draft_session/<id>/queue/move")]
+pub fn option_move<'a>(id: &str) -> &'a str {
+    let _id = id;
+    "Ok"
+}
+
+#[get("/draft_session/<id>/queue")]
+pub fn get_queue(id: &str, player: AuthenticatedPlayer) -> Json<PickQueue> {
+    let _id = id;
+    queue_of(&player)
+}
+
+/// Replaces the whole queue. Pokemon already picked or banned are left out.
+#[post(
+    "/draft_session/<id>/queue",
+    format = "application/json",
+    data = "<queue_form>"
+)]
+pub async fn set_queue(
+    id: &str,
+    queue_form: Json<PickQueue>,
+    mut player: AuthenticatedPlayer,
+    db: &State<Store>,
+) -> ApiResult<Json<PickQueue>> {
+    let _id = id;
+    store::load_draft_pool(db, &mut player.session).await?;
+
+    if let Some(pk) = queue_form.pokemon.iter().find(|pk| !player.session.is_in_draft_pool(pk)) {
+        return Err(ApiError::bad_request(
+            "pokemon_not_in_draft_set",
+            format!("Pokemon {pk} is not part of this session's draft set"),
+        ));
+    }
+
+    let queue = queue_form.0.pokemon
+        .into_iter()
+        .filter(|pk| !player.session.is_pokemon_chosen(pk))
+        .collect();
+    player.player.set_pick_queue(queue);
+    db.set_pick_queue(&player.user_id, player.player.pick_queue()).await?;
+
+    Ok(queue_of(&player))
+}
+
+#[post(
+    "/draft_session/<id>/queue/move",
+    format = "application/json",
+    data = "<move_form>"
+)]
+pub async fn move_in_queue(
+    id: &str,
+    move_form: Json<MoveInQueueRequest>,
+    mut player: AuthenticatedPlayer,
+    db: &State<Store>,
+) -> ApiResult<Json<PickQueue>> {
+    let _id = id;
+    // positions count from the queue the player sees
+    player.player.set_pick_queue(current_queue(&player));
+    if !player.player.move_in_pick_queue(move_form.pokemon_id, move_form.position) {
+        return Err(ApiError::not_found("pokemon_not_queued", "That pokemon isn't in your queue"));
+    }
+    db.set_pick_queue(&player.user_id, player.player.pick_queue()).await?;
+
+    Ok(queue_of(&player))
+}
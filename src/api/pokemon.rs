@@ -1,37 +1,231 @@
-use crate::models::pokemon::Pokemon;
+use std::sync::Arc;
+
+use crate::api::error::{ApiError, ApiResult};
+use crate::models::evolution::EvolutionFamily;
+use crate::models::pokedex::Pokedex;
+use crate::models::pokemon::{Pokemon, PokemonFilter, PokemonType};
+use crate::store::Store;
 
 use rocket::State;
+use rocket::fairing::AdHoc;
 use rocket::serde::json::Json;
 
-use surrealdb::Surreal;
-use surrealdb::engine::remote::ws::Client;
+use serde::Serialize;
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
 
 #[get("/pokemon/get/<id>")]
 // Why couldn't this be a u64?
-pub async fn get(id: &str, db: &State<Surreal<Client>>) -> Option<Json<Pokemon>> {
-    let pokemon: Option<Pokemon> = match db.select(("pokemon", id)).await {
-        Ok(p) => p,
-        Err(e) => {
-            println!("{}", e);
-            return None;
+pub async fn get(id: &str, db: &State<Store>) -> ApiResult<Json<Pokemon>> {
+    match db.get_pokemon(id).await? {
+        Some(p) => Ok(Json(p)),
+        None => Err(ApiError::not_found("pokemon_not_found", format!("No pokemon with id {id}"))),
+    }
+}
+
+#[get("/pokemon/get")]
+pub async fn list(db: &State<Store>) -> ApiResult<Json<Vec<Pokemon>>> {
+    let pokemon: Vec<Pokemon> = db.list_pokemon().await?;
+
+    Ok(Json(pokemon))
+}
+
+/// Reads the pokedex once the store is managed, for the evolution routes and
+/// the roster checks on every pick.
+pub fn pokedex_fairing() -> AdHoc {
+    AdHoc::on_ignite("Pokedex", |rocket| Box::pin(async move {
+        let pokedex = match rocket.state::<Store>() {
+            Some(db) => db.list_pokemon().await.unwrap_or_else(|e| {
+                error!("{}", e);
+                vec![]
+            }),
+            None => vec![],
+        };
+        rocket.manage(Arc::new(Pokedex::new(pokedex)))
+    }))
+}
+
+// ranked below `get` so `/pokemon/get/evolutions` still means pokemon "evolutions"
+#[get("/pokemon/<id>/evolutions", rank = 2)]
+pub fn evolutions(id: u32, pokedex: &State<Arc<Pokedex>>) -> ApiResult<Json<EvolutionFamily>> {
+    match pokedex.evolutions().family(id) {
+        Some(family) => Ok(Json(family)),
+        None => Err(ApiError::not_found("pokemon_not_found", format!("No pokemon with id {id}"))),
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, FromFormField)]
+pub enum PokemonSort {
+    #[field(value = "dex_id")]
+    DexId,
+    #[field(value = "name")]
+    Name,
+    #[field(value = "gen")]
+    Gen,
+    /// Base stat total, pokemon imported without stats count as 0.
+    #[field(value = "bst")]
+    Bst,
+}
+
+/// Query string of `GET /pokemon`. Every filter is optional, and they mean
+/// what they do in a [`PokemonFilter`].
+#[derive(Debug, FromForm)]
+pub struct PokemonQuery {
+    /// Start of the name, any case.
+    name: Option<String>,
+    /// Lets `name` match anywhere in the name, and with a typo or two.
+    #[field(default = false)]
+    fuzzy: bool,
+    type1: Option<PokemonType>,
+    type2: Option<PokemonType>,
+    /// Either of the two types.
+    #[field(name = "type")]
+    any_type: Option<PokemonType>,
+    gen: Option<u8>,
+    is_legendary: Option<bool>,
+    is_mythic: Option<bool>,
+    #[field(default = false)]
+    base_only: bool,
+    forms: Option<bool>,
+    /// Dex order by default, closest name first for a fuzzy search.
+    sort: Option<PokemonSort>,
+    #[field(default = false)]
+    desc: bool,
+    #[field(default = 0)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PokemonPage {
+    /// How many pokemon matched, across every page.
+    total: usize,
+    offset: usize,
+    limit: usize,
+    /// Where the next page starts, missing on the last one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_offset: Option<usize>,
+    pokemon: Vec<Pokemon>,
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (diagonal + usize::from(ca != cb)).min(row[j] + 1).min(row[j + 1] + 1);
+            diagonal = row[j + 1];
+            row[j + 1] = next;
         }
-    };
+    }
+    row[b.len()]
+}
+
+/// How closely `name` matches `query`, lower is closer: 0 for a prefix, 1
+/// for anywhere else in the name, then 1 more per typo. `None` for no match.
+fn name_score(name: &str, query: &str, fuzzy: bool) -> Option<usize> {
+    let (name, query) = (name.to_lowercase(), query.to_lowercase());
+    if name.starts_with(&query) {
+        return Some(0);
+    }
+    if !fuzzy {
+        return None;
+    }
+    if name.contains(&query) {
+        return Some(1);
+    }
 
-    match pokemon {
-        Some(p) => Some(Json(p)),
-        None => None,
+    // one typo per four letters, so short queries don't match everything. They
+    // only count against the start of the name, the way a prefix would
+    let query: Vec<char> = query.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let allowed = query.len() / 4;
+    if allowed == 0 {
+        return None;
     }
+    (query.len().saturating_sub(1)..=query.len() + 1)
+        .map(|len| edit_distance(&query, &name[..len.min(name.len())]))
+        .min()
+        .filter(|d| *d <= allowed)
+        .map(|d| 1 + d)
 }
 
-#[get("/pokemon/get")]
-pub async fn list(db: &State<Surreal<Client>>) -> Json<Vec<Pokemon>> {
-    let pokemon: Vec<Pokemon> = match db.select("pokemon").await {
-        Ok(p) => p,
-        Err(e) => {
-            println!("{}", e);
-            Vec::new()
+impl PokemonQuery {
+    fn filter(&self) -> PokemonFilter {
+        PokemonFilter {
+            gens: self.gen.into_iter().collect(),
+            types: self.any_type.into_iter().collect(),
+            type1: self.type1,
+            type2: self.type2,
+            is_legendary: self.is_legendary,
+            is_mythic: self.is_mythic,
+            base_only: self.base_only,
+            forms: self.forms,
+            ..Default::default()
+        }
+    }
+
+    /// Filters and sorts `pokedex`, then cuts out the page asked for.
+    fn run(&self, pokedex: &Pokedex) -> PokemonPage {
+        let filter = self.filter();
+        let mut found: Vec<(usize, &Pokemon)> = pokedex
+            .iter()
+            .filter(|p| filter.matches(p))
+            .filter_map(|p| match &self.name {
+                Some(q) => name_score(&p.name, q, self.fuzzy).map(|score| (score, p)),
+                None => Some((0, p)),
+            })
+            .collect();
+
+        // forms sit right after their species in dex order
+        match self.sort {
+            Some(PokemonSort::DexId) => found.sort_by_key(|(_, p)| (p.species_id(), p.dex_id)),
+            Some(PokemonSort::Name) => found.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name)),
+            Some(PokemonSort::Gen) => found.sort_by_key(|(_, p)| (p.gen, p.species_id(), p.dex_id)),
+            Some(PokemonSort::Bst) => {
+                found.sort_by_key(|(_, p)| (p.stats.map_or(0, |s| s.total()), p.species_id(), p.dex_id))
+            },
+            None => found.sort_by_key(|(score, p)| (*score, p.species_id(), p.dex_id)),
         }
-    };
+        if self.desc {
+            found.reverse();
+        }
+
+        let total = found.len();
+        let limit = self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let pokemon: Vec<Pokemon> = found.into_iter().skip(self.offset).take(limit).map(|(_, p)| p.clone()).collect();
+        let next_offset = Some(self.offset + pokemon.len()).filter(|next| *next < total);
+
+        PokemonPage {
+            total,
+            offset: self.offset,
+            limit,
+            next_offset,
+            pokemon,
+        }
+    }
+}
+
+#[get("/pokemon?<query..>")]
+pub fn search(query: PokemonQuery, pokedex: &State<Arc<Pokedex>>) -> Json<PokemonPage> {
+    Json(query.run(pokedex))
+}
 
-    Json(pokemon)
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_name_score() {
+        assert_eq!(name_score("Charmander", "char", false), Some(0));
+        assert_eq!(name_score("Charmander", "mander", false), None);
+        assert_eq!(name_score("Charmander", "mander", true), Some(1));
+        assert_eq!(name_score("Charmander", "charmnder", true), Some(2));
+        assert_eq!(name_score("Charmander", "chrmeleon", true), None);
+        assert_eq!(name_score("Squirtle", "bulba", true), None);
+        assert_eq!(name_score("Squirtle", "sq", true), Some(0));
+        assert_eq!(name_score("Squirtle", "xq", true), None);
+    }
 }
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::models::draft::{DraftPhase, DraftState};
+
+use rocket::tokio::sync::broadcast;
+
+use serde::{Deserialize, Serialize};
+
+// How many events a slow subscriber can fall behind before it starts missing them
+const CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DraftEvent {
+    PlayerJoined { player: String, order_in_session: u32 },
+    ReadyToggled { player: String, ready: bool, draft_state: DraftState },
+    DraftStarted,
+    PokemonPicked { player: String, pokemon_id: u32 },
+    PokemonBanned { player: String, pokemon_id: u32 },
+    PhaseChanged { phase: DraftPhase },
+    DraftEnded,
+    DraftPaused,
+    DraftResumed,
+    DraftCancelled,
+    PlayerKicked { player: String },
+    PlayersReordered { players: Vec<String> },
+    AcceptingPlayersChanged { accepting_players: bool },
+    /// Followed by the automatic pick or ban, if there was one.
+    TurnTimedOut { player: String },
+    /// The host took back a pick or ban, it's `player`'s turn again.
+    SelectionUndone { player: String, pokemon_id: u32, action: DraftPhase },
+    ChatMessage { player: String, message: String },
+}
+
+impl DraftEvent {
+    /// Name used for the SSE `event:` field so clients can listen per type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DraftEvent::PlayerJoined { .. } => "player_joined",
+            DraftEvent::ReadyToggled { .. } => "ready_toggled",
+            DraftEvent::DraftStarted => "draft_started",
+            DraftEvent::PokemonPicked { .. } => "pokemon_picked",
+            DraftEvent::PokemonBanned { .. } => "pokemon_banned",
+            DraftEvent::PhaseChanged { .. } => "phase_changed",
+            DraftEvent::DraftEnded => "draft_ended",
+            DraftEvent::DraftPaused => "draft_paused",
+            DraftEvent::DraftResumed => "draft_resumed",
+            DraftEvent::DraftCancelled => "draft_cancelled",
+            DraftEvent::PlayerKicked { .. } => "player_kicked",
+            DraftEvent::PlayersReordered { .. } => "players_reordered",
+            DraftEvent::AcceptingPlayersChanged { .. } => "accepting_players_changed",
+            DraftEvent::TurnTimedOut { .. } => "turn_timed_out",
+            DraftEvent::SelectionUndone { .. } => "selection_undone",
+            DraftEvent::ChatMessage { .. } => "chat_message",
+        }
+    }
+}
+
+/// A [`DraftEvent`] as it goes out to subscribers. `seq` is the session's
+/// `action_count` once the action behind the event was saved, so a client
+/// holding a snapshot can skip what the snapshot already shows. Chat isn't
+/// part of the session and has no `seq`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionEvent {
+    pub seq: Option<u32>,
+    pub event: DraftEvent,
+}
+
+impl SessionEvent {
+    /// Whether a snapshot taken at `action_count` already shows this event.
+    pub fn seen_at(&self, action_count: u32) -> bool {
+        self.seq.is_some_and(|seq| seq <= action_count)
+    }
+}
+
+/// One broadcast channel per draft session. Channels are created by the first
+/// subscriber and dropped once a publish finds nobody listening. Clones share
+/// the same channels.
+#[derive(Default, Clone)]
+pub struct EventHub {
+    channels: Arc<Mutex<HashMap<String, broadcast::Sender<SessionEvent>>>>,
+}
+
+impl EventHub {
+    pub fn subscribe(&self, session_id: &str) -> broadcast::Receiver<SessionEvent> {
+        let mut channels = self.channels.lock().expect("event hub lock poisoned");
+        channels
+            .entry(session_id.to_string())
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    pub fn publish(&self, session_id: &str, seq: Option<u32>, events: Vec<DraftEvent>) {
+        let mut channels = self.channels.lock().expect("event hub lock poisoned");
+        let sender = match channels.get(session_id) {
+            Some(s) => s,
+            None => return,
+        };
+
+        for event in events {
+            if sender.send(SessionEvent { seq, event }).is_err() {
+                channels.remove(session_id);
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rocket::async_test]
+    async fn test_publish_reaches_only_that_session() {
+        let hub = EventHub::default();
+        let mut rx = hub.subscribe("one");
+        let mut other = hub.subscribe("two");
+
+        hub.publish("one", Some(3), vec![DraftEvent::DraftStarted, DraftEvent::DraftEnded]);
+
+        assert_eq!(rx.recv().await.unwrap(), SessionEvent { seq: Some(3), event: DraftEvent::DraftStarted });
+        assert_eq!(rx.recv().await.unwrap(), SessionEvent { seq: Some(3), event: DraftEvent::DraftEnded });
+        assert!(other.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_seen_at() {
+        let picked = SessionEvent { seq: Some(4), event: DraftEvent::DraftStarted };
+        assert!(picked.seen_at(4));
+        assert!(picked.seen_at(5));
+        assert!(!picked.seen_at(3));
+
+        let chat = SessionEvent { seq: None, event: DraftEvent::ChatMessage { player: "Ash".into(), message: "gl".into() } };
+        assert!(!chat.seen_at(10));
+    }
+
+    #[test]
+    fn test_channel_dropped_without_subscribers() {
+        let hub = EventHub::default();
+        drop(hub.subscribe("one"));
+
+        hub.publish("one", None, vec![DraftEvent::DraftStarted]);
+
+        assert!(hub.channels.lock().unwrap().is_empty());
+    }
+}
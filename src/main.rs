@@ -2,8 +2,17 @@
 extern crate surrealdb;
 
 mod api;
+mod events;
 mod models;
-use api::{draft_session, pokemon, draft_set, draft_rules, CORS};
+mod store;
+mod timer;
+use api::{coverage, draft_room, export, draft_session, pick_queue, pokemon, draft_set, draft_rules, error, CORS};
+use events::EventHub;
+use store::{MemoryStore, Store, SurrealStore};
+
+use std::sync::Arc;
+
+use rocket::{Build, Rocket};
 
 use surrealdb::Surreal;
 use surrealdb::opt::auth::{Root, Database};
@@ -27,6 +36,21 @@ enum DBUserType {
     Database
 }
 
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+enum StoreBackend {
+    #[default]
+    Surreal,
+    Memory,
+}
+
+#[derive(Deserialize)]
+struct StoreConfig {
+    #[serde(default)]
+    store: StoreBackend,
+    memory_seed: Option<String>,
+}
+
 async fn init_db(conf: DBConfig) -> Surreal<Client> {
     let db = match Surreal::new::<Ws>(conf.surreal_addr).await {
         Ok(f) => f,
@@ -51,19 +75,35 @@ async fn init_db(conf: DBConfig) -> Surreal<Client> {
     db
 }
 
-#[launch]
-async fn rocket() -> _ {
-    let rocket = rocket::build();
+async fn init_store(rocket: &Rocket<Build>) -> Store {
     let figment = rocket.figment();
+    let config: StoreConfig = figment.extract().expect("Unable to read store configuration");
 
-    let config: DBConfig = figment.extract().expect("Unable to read surreal db configuration");
-    let db = init_db(config).await;
+    match config.store {
+        StoreBackend::Surreal => {
+            let db_config: DBConfig = figment.extract().expect("Unable to read surreal db configuration");
+            Arc::new(SurrealStore::new(init_db(db_config).await))
+        },
+        StoreBackend::Memory => match config.memory_seed {
+            Some(path) => Arc::new(MemoryStore::from_seed_file(&path).await.expect("Unable to seed memory store")),
+            None => Arc::new(MemoryStore::new()),
+        },
+    }
+}
 
-    rocket.manage(db)
+fn mount_api(rocket: Rocket<Build>, store: Store) -> Rocket<Build> {
+    rocket.manage(store)
+        .manage(EventHub::default())
         .mount("/api/v1", routes![pokemon::get])
         .mount("/api/v1", routes![pokemon::list])
+        .mount("/api/v1", routes![pokemon::search])
+        .mount("/api/v1", routes![pokemon::evolutions])
         .mount("/api/v1", routes![draft_set::get_pokemon_draft_set])
         .mount("/api/v1", routes![draft_set::list_pokemon_draft_set])
+        .mount("/api/v1", routes![draft_set::option_draft_set])
+        .mount("/api/v1", routes![draft_set::create_draft_set])
+        .mount("/api/v1", routes![draft_set::update_draft_set])
+        .mount("/api/v1", routes![draft_set::delete_draft_set])
         .mount("/api/v1", routes![draft_rules::get_draft_rules])
         .mount("/api/v1", routes![draft_rules::list_draft_rules])
         .mount("/api/v1", routes![draft_rules::create_draft_rules])
@@ -71,11 +111,923 @@ async fn rocket() -> _ {
         .mount("/api/v1", routes![draft_session::create_draft_session])
         .mount("/api/v1", routes![draft_session::option_draft_session])
         .mount("/api/v1", routes![draft_session::update_draft_session])
+        .mount("/api/v1", routes![draft_session::draft_session_events])
+        .mount("/api/v1", routes![draft_room::draft_room])
         .mount("/api/v1", routes![draft_session::create_user])
         .mount("/api/v1", routes![draft_session::option_create_user])
         .mount("/api/v1", routes![draft_session::select_pokemon])
         .mount("/api/v1", routes![draft_session::option_select_pokemon])
         .mount("/api/v1", routes![draft_session::toggle_ready])
+        .mount("/api/v1", routes![draft_session::option_ready])
         .mount("/api/v1", routes![draft_session::start])
+        .mount("/api/v1", routes![draft_session::option_start])
+        .mount("/api/v1", routes![draft_session::option_host_action])
+        .mount("/api/v1", routes![draft_session::pause])
+        .mount("/api/v1", routes![draft_session::resume])
+        .mount("/api/v1", routes![draft_session::cancel])
+        .mount("/api/v1", routes![draft_session::lock])
+        .mount("/api/v1", routes![draft_session::unlock])
+        .mount("/api/v1", routes![draft_session::option_player])
+        .mount("/api/v1", routes![draft_session::kick])
+        .mount("/api/v1", routes![draft_session::reorder])
+        .mount("/api/v1", routes![draft_session::undo])
+        .mount("/api/v1", routes![draft_session::get_history])
+        .mount("/api/v1", routes![draft_session::replay])
+        .mount("/api/v1", routes![pick_queue::get_queue])
+        .mount("/api/v1", routes![pick_queue::set_queue])
+        .mount("/api/v1", routes![pick_queue::option_move])
+        .mount("/api/v1", routes![pick_queue::move_in_queue])
+        .mount("/api/v1", routes![coverage::get_coverage])
+        .mount("/api/v1", routes![export::export_player])
+        .mount("/api/v1", routes![export::export_league])
+        .register("/", error::catchers())
         .attach(CORS)
+        .attach(pokemon::pokedex_fairing())
+        .attach(timer::fairing())
+}
+
+#[launch]
+async fn rocket() -> _ {
+    let rocket = rocket::build();
+    let store = init_store(&rocket).await;
+
+    mount_api(rocket, store)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use rocket::http::{ContentType, Header, Status};
+    use rocket::local::asynchronous::{Client, LocalRequest};
+    use rocket::serde::json::{json, serde_json, Value};
+
+    use models::draft::engine;
+    use models::pokedex::Pokedex;
+    use models::pokemon::Pokemon;
+
+    async fn client() -> Client {
+        let store = MemoryStore::from_seed_file("scripts/memory_seed.json").await.expect("seed file");
+        Client::tracked(mount_api(rocket::build(), Arc::new(store))).await.expect("valid rocket")
+    }
+
+    fn unwrap_id(data: &Value) -> String {
+        data["id"]["id"]["String"].as_str().expect("string record key").to_string()
+    }
+
+    fn bearer(player: &Value) -> Header<'static> {
+        Header::new("Authorization", format!("Bearer {}", player["token"].as_str().expect("player token")))
+    }
+
+    async fn find_by_name(client: &Client, uri: &str, name: &str) -> String {
+        let list: Value = client.get(uri).dispatch().await.into_json().await.unwrap();
+        let found = list.as_array().unwrap().iter().find(|x| x["name"] == name).unwrap();
+        unwrap_id(found)
+    }
+
+    /// Returns the session id and the host token.
+    async fn create_session(client: &Client, rules: &str) -> (String, Value) {
+        create_session_with_set(client, "Debug Set", rules).await
+    }
+
+    async fn create_session_with_set(client: &Client, set: &str, rules: &str) -> (String, Value) {
+        let set_id = find_by_name(client, "/api/v1/draft_set", set).await;
+        let rules_id = find_by_name(client, "/api/v1/draft_rules", rules).await;
+
+        let session: Value = client.post("/api/v1/draft_session/create")
+            .json(&json!({
+                "name": "TEST",
+                "draft_set": set_id,
+                "draft_rules": rules_id,
+                "min_num_players": 2,
+                "max_num_players": 3,
+            }))
+            .dispatch().await
+            .into_json().await.unwrap();
+        (unwrap_id(&session), json!({ "token": session["host_token"] }))
+    }
+
+    async fn join(client: &Client, session_id: &str, name: &str) -> Value {
+        client.post(format!("/api/v1/draft_session/{session_id}/create-user"))
+            .json(&json!({ "name": name }))
+            .dispatch().await
+            .into_json().await.unwrap()
+    }
+
+    /// Has Player 1 and Player 2 join and ready up, then starts the draft.
+    /// Returns the session id and the two players' tokens.
+    async fn start_two_player_draft(client: &Client, set: &str, rules: &str) -> (String, Value, Value) {
+        let (session_id, host) = create_session_with_set(client, set, rules).await;
+        let url = |path: &str| format!("/api/v1/draft_session/{session_id}/{path}");
+        let p1 = join(client, &session_id, "Player 1").await;
+        let p2 = join(client, &session_id, "Player 2").await;
+        for player in [&p1, &p2] {
+            client.post(url("ready")).header(ContentType::JSON).header(bearer(player)).dispatch().await;
+        }
+        let resp = client.post(url("start")).header(ContentType::JSON).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        (session_id, p1, p2)
+    }
+
+    fn pick<'c>(client: &'c Client, session_id: &str, player: &Value, pokemon_id: u32) -> LocalRequest<'c> {
+        client.post(format!("/api/v1/draft_session/{session_id}/select-pokemon"))
+            .header(bearer(player))
+            .json(&json!({ "pokemon_id": pokemon_id, "action": "Pick" }))
+    }
+
+    #[rocket::async_test]
+    async fn test_full_game_pick_first_in_memory() {
+        let client = client().await;
+        let (session_id, host) = create_session(&client, "Intergration Test Snake Pick First").await;
+
+        let mut players = vec![];
+        for i in 1..=3 {
+            let player = join(&client, &session_id, &format!("Player {i}")).await;
+            let resp = client.post(format!("/api/v1/draft_session/{session_id}/ready"))
+                .header(ContentType::JSON)
+                .header(bearer(&player))
+                .dispatch().await;
+            assert_eq!(resp.status(), Status::Ok);
+            players.push(player);
+        }
+
+        let resp = client.post(format!("/api/v1/draft_session/{session_id}/select-pokemon"))
+            .header(bearer(&players[0]))
+            .json(&json!({ "pokemon_id": 1, "action": "Pick" }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Conflict);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "draft_not_started");
+
+        let resp = client.post(format!("/api/v1/draft_session/{session_id}/start"))
+            .header(ContentType::JSON)
+            .header(bearer(&host))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+
+        // Debug Set is dex 1-9
+        let resp = client.post(format!("/api/v1/draft_session/{session_id}/select-pokemon"))
+            .header(bearer(&players[0]))
+            .json(&json!({ "pokemon_id": 150, "action": "Pick" }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::BadRequest);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "pokemon_not_in_draft_set");
+
+        for (i, player) in players.iter().enumerate() {
+            let resp = client.post(format!("/api/v1/draft_session/{session_id}/select-pokemon"))
+                .header(bearer(player))
+                .json(&json!({ "pokemon_id": i + 1, "action": "Pick" }))
+                .dispatch().await;
+            assert_eq!(resp.status(), Status::Ok);
+        }
+
+        let update: Value = client.get(format!("/api/v1/draft_session/{session_id}/update"))
+            .dispatch().await
+            .into_json().await.unwrap();
+        assert_eq!(update, json!({
+            "current_phase": "Pick",
+            "picked": [
+                {"pokemon_id": 1, "player": "Player 1"},
+                {"pokemon_id": 2, "player": "Player 2"},
+                {"pokemon_id": 3, "player": "Player 3"},
+            ],
+            "banned": [],
+            "current_player": null,
+            "state": "Ended",
+            "players": [
+                {"name": "Player 1", "pokemon": [1], "ready": true},
+                {"name": "Player 2", "pokemon": [2], "ready": true},
+                {"name": "Player 3", "pokemon": [3], "ready": true},
+            ],
+            "action_count": 10,
+        }));
+    }
+
+    #[rocket::async_test]
+    async fn test_session_hides_secrets() {
+        let client = client().await;
+        let set_id = find_by_name(&client, "/api/v1/draft_set", "Debug Set").await;
+        let rules_id = find_by_name(&client, "/api/v1/draft_rules", "Intergration Test Snake").await;
+        let created: Value = client.post("/api/v1/draft_session/create")
+            .json(&json!({ "name": "TEST", "draft_set": set_id, "draft_rules": rules_id, "min_num_players": 2, "max_num_players": 3 }))
+            .dispatch().await
+            .into_json().await.unwrap();
+        let session_id = unwrap_id(&created);
+
+        let stored = client.rocket().state::<Store>().unwrap().get_session(&session_id).await.unwrap().unwrap();
+        assert_ne!(stored.order_seed, 0);
+
+        let fetched: Value = client.get(format!("/api/v1/draft_session/{session_id}")).dispatch().await.into_json().await.unwrap();
+        let replayed: Value = client.get(format!("/api/v1/draft_session/{session_id}/replay")).dispatch().await.into_json().await.unwrap();
+        for session in [&created, &fetched, &replayed] {
+            assert_eq!(session["order_seed"], 0);
+            assert!(session["host_key_hash"].is_null());
+        }
+    }
+
+    #[rocket::async_test]
+    async fn test_player_routes_need_token() {
+        let client = client().await;
+        let (session_id, _) = create_session(&client, "Intergration Test Snake Pick First").await;
+        let (other_session, _) = create_session(&client, "Intergration Test Snake Pick First").await;
+        let player = join(&client, &session_id, "Player 1").await;
+        let stranger = join(&client, &other_session, "Player 1").await;
+        let ready = format!("/api/v1/draft_session/{session_id}/ready");
+
+        let resp = client.post(&ready).header(ContentType::JSON).dispatch().await;
+        assert_eq!(resp.status(), Status::Unauthorized);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "missing_token");
+
+        let resp = client.post(&ready)
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", "Bearer nonsense"))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Unauthorized);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "invalid_token");
+
+        // a real token, but for a player in another session
+        let resp = client.post(&ready).header(ContentType::JSON).header(bearer(&stranger)).dispatch().await;
+        assert_eq!(resp.status(), Status::NotFound);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "user_not_found");
+
+        let token = player["token"].as_str().unwrap();
+        let (user_key, _) = token.split_once('.').unwrap();
+        let resp = client.post(&ready)
+            .header(ContentType::JSON)
+            .header(Header::new("Authorization", format!("Bearer {user_key}.{}", uuid::Uuid::new_v4())))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Unauthorized);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "access_denied");
+
+        let resp = client.post(&ready).header(ContentType::JSON).header(bearer(&player)).dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+    }
+
+    #[rocket::async_test]
+    async fn test_host_controls() {
+        let client = client().await;
+        let (session_id, host) = create_session(&client, "Intergration Test Snake Pick First").await;
+        let players = [
+            join(&client, &session_id, "Player 1").await,
+            join(&client, &session_id, "Player 2").await,
+        ];
+        let url = |path: &str| format!("/api/v1/draft_session/{session_id}/{path}");
+        let user_key = |player: &Value| player["user_id"]["id"]["String"].as_str().unwrap().to_string();
+
+        // players aren't hosts, not even the first one
+        let resp = client.post(url("lock")).header(bearer(&players[0])).dispatch().await;
+        assert_eq!(resp.status(), Status::Forbidden);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "not_host");
+
+        let resp = client.post(url("lock")).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let resp = client.post(url("create-user")).json(&json!({ "name": "Player 3" })).dispatch().await;
+        assert_eq!(resp.status(), Status::Conflict);
+        let resp = client.post(url("unlock")).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let late = join(&client, &session_id, "Player 3").await;
+
+        let resp = client.post(url("order"))
+            .header(bearer(&host))
+            .json(&json!({ "players": [user_key(&late), user_key(&players[0]), user_key(&players[1])] }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let update: Value = resp.into_json().await.unwrap();
+        assert_eq!(update["current_player"], "Player 3");
+
+        let resp = client.delete(url(&format!("players/{}", user_key(&players[1]))))
+            .header(bearer(&host))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let resp = client.post(url("ready")).header(ContentType::JSON).header(bearer(&players[1])).dispatch().await;
+        assert_eq!(resp.status(), Status::NotFound);
+
+        let resp = client.post(url("start")).header(ContentType::JSON).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Conflict);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "players_not_ready");
+
+        for player in [&late, &players[0]] {
+            let resp = client.post(url("ready")).header(ContentType::JSON).header(bearer(player)).dispatch().await;
+            assert_eq!(resp.status(), Status::Ok);
+        }
+        let resp = client.post(url("start")).header(ContentType::JSON).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let resp = client.post(url("pause")).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let resp = client.post(url("select-pokemon"))
+            .header(bearer(&late))
+            .json(&json!({ "pokemon_id": 1, "action": "Pick" }))
+            .dispatch().await;
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "draft_paused");
+
+        let resp = client.post(url("resume")).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let resp = client.post(url("cancel")).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let resp = client.post(url("resume")).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Conflict);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "invalid_transition");
+
+        let update: Value = client.get(url("update")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(update["state"], "Cancelled");
+        let names: Vec<&str> = update["players"].as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["Player 3", "Player 1"]);
+    }
+
+    #[rocket::async_test]
+    async fn test_host_undo() {
+        let client = client().await;
+        let (session_id, host) = create_session(&client, "Intergration Test Snake Pick First").await;
+        let url = |path: &str| format!("/api/v1/draft_session/{session_id}/{path}");
+        let p1 = join(&client, &session_id, "Player 1").await;
+        let p2 = join(&client, &session_id, "Player 2").await;
+        for player in [&p1, &p2] {
+            client.post(url("ready")).header(ContentType::JSON).header(bearer(player)).dispatch().await;
+        }
+        client.post(url("start")).header(ContentType::JSON).header(bearer(&host)).dispatch().await;
+
+        let resp = client.post(url("undo")).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Conflict);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "nothing_to_undo");
+
+        let resp = client.post(url("select-pokemon")).header(bearer(&p1))
+            .json(&json!({ "pokemon_id": 1, "action": "Pick" }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+
+        let resp = client.post(url("undo")).header(bearer(&p1)).dispatch().await;
+        assert_eq!(resp.status(), Status::Forbidden);
+
+        let resp = client.post(url("undo")).header(bearer(&host)).dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let update: Value = resp.into_json().await.unwrap();
+        assert_eq!(update["current_player"], "Player 1");
+        assert_eq!(update["players"][0]["pokemon"], json!([]));
+        assert_eq!(update["picked"], json!([]));
+
+        let session: Value = client.get(url("")).dispatch().await.into_json().await.unwrap();
+        let history: Vec<&str> = session["history"].as_array().unwrap().iter().map(|h| h["type"].as_str().unwrap()).collect();
+        assert_eq!(history, vec!["Selection", "Undo"]);
+
+        // the pokemon is up for grabs again
+        let resp = client.post(url("select-pokemon")).header(bearer(&p1))
+            .json(&json!({ "pokemon_id": 1, "action": "Pick" }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+
+        let log: Value = client.get(url("history")).dispatch().await.into_json().await.unwrap();
+        let log = log.as_array().unwrap();
+        let actions: Vec<&str> = log.iter().map(|e| e["action"]["type"].as_str().unwrap()).collect();
+        assert_eq!(actions, vec!["join", "join", "toggle_ready", "toggle_ready", "start", "select", "undo", "select"]);
+        assert_eq!(log[0]["actor"], json!({ "type": "player", "name": "Player 1" }));
+        assert_eq!(log[6]["actor"], json!({ "type": "host" }));
+        assert_eq!(log[7]["turn"], 0);
+        assert_eq!(log[7]["events"][0]["type"], "pokemon_picked");
+        assert!(log[0]["action"]["user"]["key_hash"] == "");
+
+        let replayed: Value = client.get(url("replay?turn=0")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(replayed["draft_state"], "InProgress");
+        assert_eq!(replayed["picked"], json!([]));
+        let replayed: Value = client.get(url("replay")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(replayed["picked"][0]["pokemon_id"], 1);
+        assert_eq!(replayed["players"][0]["selected_pokemon"], json!([1]));
+    }
+
+    #[rocket::async_test]
+    async fn test_point_budget() {
+        let client = client().await;
+        let (session_id, p1, p2) =
+            start_two_player_draft(&client, "Debug Point Set", "Intergration Test Point Budget").await;
+        let url = |path: &str| format!("/api/v1/draft_session/{session_id}/{path}");
+
+        let resp: Value = pick(&client, &session_id, &p1, 6).dispatch().await.into_json().await.unwrap();
+        assert_eq!(resp["points_remaining"], 3);
+        pick(&client, &session_id, &p2, 3).dispatch().await;
+
+        let update: Value = client.get(url("update")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(update["players"][0]["points_remaining"], 3);
+        assert_eq!(update["players"][1]["points_remaining"], 3);
+
+        // snake, so Player 2 goes again
+        let resp = pick(&client, &session_id, &p2, 9).dispatch().await;
+        assert_eq!(resp.status(), Status::Conflict);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "over_budget");
+        assert_eq!(error["details"], json!({ "cost": 8, "remaining": 3 }));
+
+        let resp: Value = pick(&client, &session_id, &p2, 7).dispatch().await.into_json().await.unwrap();
+        assert_eq!(resp["points_remaining"], 0);
+
+        let set_id = find_by_name(&client, "/api/v1/draft_set", "Debug Point Set").await;
+        let set: Value = client.get(format!("/api/v1/draft_set/{set_id}")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(set["members"][5], json!({ "dex_id": 6, "points": 8 }));
+
+        // no budget, no points
+        let (session_id, _) = create_session(&client, "Intergration Test Snake").await;
+        join(&client, &session_id, "Player 1").await;
+        let update: Value = client.get(format!("/api/v1/draft_session/{session_id}/update"))
+            .dispatch().await
+            .into_json().await.unwrap();
+        assert!(update["players"][0].get("points_remaining").is_none());
+    }
+
+    #[rocket::async_test]
+    async fn test_tier_limits() {
+        let client = client().await;
+        let (session_id, p1, p2) = start_two_player_draft(&client, "Debug Tier Set", "Intergration Test Tiers").await;
+
+        // every final evolution is OU, one each
+        assert_eq!(pick(&client, &session_id, &p1, 3).dispatch().await.status(), Status::Ok);
+        assert_eq!(pick(&client, &session_id, &p2, 6).dispatch().await.status(), Status::Ok);
+        let resp = pick(&client, &session_id, &p2, 9).dispatch().await;
+        assert_eq!(resp.status(), Status::Conflict);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "tier_full");
+        assert_eq!(error["details"], json!({ "tier": "OU", "max_picks": 1 }));
+        assert_eq!(pick(&client, &session_id, &p2, 8).dispatch().await.status(), Status::Ok);
+
+        let set_id = find_by_name(&client, "/api/v1/draft_set", "Debug Tier Set").await;
+        let set: Value = client.get(format!("/api/v1/draft_set/{set_id}")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(set["members"][0], json!({ "dex_id": 1, "tier": "UU" }));
+        assert_eq!(set["members"][2], json!({ "dex_id": 3, "tier": "OU" }));
+    }
+
+    #[rocket::async_test]
+    async fn test_roster_constraints() {
+        let client = client().await;
+        let resp = client.post("/api/v1/draft_rules/create")
+            .json(&json!({
+                "name": "One Per Line",
+                "picks_per_round": 1,
+                "bans_per_round": 0,
+                "max_pokemon": 2,
+                "starting_phase": "Pick",
+                "turn_type": "RoundRobin",
+                "roster_constraints": { "one_per_evolution_line": true, "required_types": ["WATER"] },
+            }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+
+        let (session_id, p1, p2) = start_two_player_draft(&client, "Debug Set", "One Per Line").await;
+
+        assert_eq!(pick(&client, &session_id, &p1, 1).dispatch().await.status(), Status::Ok);
+        assert_eq!(pick(&client, &session_id, &p2, 8).dispatch().await.status(), Status::Ok);
+
+        // Venusaur evolves from Bulbasaur's line
+        let resp = pick(&client, &session_id, &p1, 3).dispatch().await;
+        assert_eq!(resp.status(), Status::Conflict);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "roster_constraint");
+        assert_eq!(error["details"], json!({ "constraint": "one_per_evolution_line", "pokemon_id": 1 }));
+
+        // last pick, and still no water type
+        let error: Value = pick(&client, &session_id, &p1, 4).dispatch().await.into_json().await.unwrap();
+        assert_eq!(error["details"], json!({ "constraint": "required_types", "missing": ["WATER"] }));
+
+        assert_eq!(pick(&client, &session_id, &p1, 7).dispatch().await.status(), Status::Ok);
+    }
+
+    #[rocket::async_test]
+    async fn test_team_coverage() {
+        let client = client().await;
+        // two picks each in snake order
+        let (session_id, p1, p2) = start_two_player_draft(&client, "Debug Set", "Intergration Test Two Picks").await;
+        let url = |path: &str| format!("/api/v1/draft_session/{session_id}/{path}");
+        // Charizard and Squirtle for player 1
+        for (player, pokemon_id) in [(&p1, 6), (&p2, 1), (&p2, 4), (&p1, 7)] {
+            assert_eq!(pick(&client, &session_id, player, pokemon_id).dispatch().await.status(), Status::Ok);
+        }
+
+        let user = p1["user_id"]["id"]["String"].as_str().unwrap();
+        let coverage: Value = client.get(url(&format!("players/{user}/coverage")))
+            .dispatch().await
+            .into_json().await.unwrap();
+        assert_eq!(coverage["pokemon"], json!([6, 7]));
+        assert_eq!(coverage["weaknesses"], json!(["ELECTRIC", "ROCK"]));
+        let ground = coverage["defense"].as_array().unwrap().iter().find(|d| d["type"] == "GROUND").unwrap();
+        assert_eq!(ground["immune"], json!([6]));
+        assert_eq!(ground["weak"], json!([]));
+        let rock = coverage["offense"].as_array().unwrap().iter().find(|o| o["type"] == "ROCK").unwrap();
+        assert_eq!(rock["super_effective"], json!([7]));
+
+        let resp = client.get(url("players/nobody/coverage")).dispatch().await;
+        assert_eq!(resp.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_showdown_export() {
+        let client = client().await;
+        let (session_id, p1, p2) = start_two_player_draft(&client, "Debug Set", "Intergration Test Two Picks").await;
+        let url = |path: &str| format!("/api/v1/draft_session/{session_id}/{path}");
+        for (player, pokemon_id) in [(&p1, 6), (&p2, 1), (&p2, 4), (&p1, 7)] {
+            pick(&client, &session_id, player, pokemon_id).dispatch().await;
+        }
+
+        let user = p1["user_id"]["id"]["String"].as_str().unwrap();
+        let resp = client.get(url(&format!("players/{user}/export?format=showdown"))).dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        assert_eq!(resp.content_type(), Some(ContentType::Plain));
+        assert_eq!(resp.into_string().await.unwrap(), "Charizard\nAbility: Blaze\n\nSquirtle\nAbility: Torrent\n");
+
+        let league = client.get(url("export?format=showdown")).dispatch().await.into_string().await.unwrap();
+        assert_eq!(league, concat!(
+            "=== Player 1 ===\n\nCharizard\nAbility: Blaze\n\nSquirtle\nAbility: Torrent\n\n",
+            "=== Player 2 ===\n\nBulbasaur\nAbility: Overgrow\n\nCharmander\nAbility: Blaze\n",
+        ));
+
+        let resp = client.get(url("export?format=pokepaste")).dispatch().await;
+        assert_eq!(resp.status(), Status::BadRequest);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "unsupported_format");
+        assert_eq!(error["details"], json!({ "formats": ["showdown"] }));
+    }
+
+    #[rocket::async_test]
+    async fn test_draft_set_crud() {
+        let client = client().await;
+
+        let set: Value = client.post("/api/v1/draft_set/create")
+            .json(&json!({
+                "name": "Fire and Water",
+                "filter": { "types": ["FIRE", "WATER"], "base_only": true, "include": [6], "exclude": [7] },
+                "pokemon": [{ "dex_id": 9, "points": 4 }],
+            }))
+            .dispatch().await
+            .into_json().await.unwrap();
+        assert_eq!(set["name"], "Fire and Water");
+        assert_eq!(set["pokemon"]["Ids"], json!([4, 6, 9]));
+        assert_eq!(set["members"][2], json!({ "dex_id": 9, "points": 4 }));
+        let id = unwrap_id(&set);
+        let url = format!("/api/v1/draft_set/{id}");
+        let owner = json!({ "token": set["owner_token"] });
+
+        let resp = client.post("/api/v1/draft_set/create")
+            .json(&json!({ "name": "Missing", "pokemon": [1, 999] }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::BadRequest);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "pokemon_not_found");
+        assert_eq!(error["details"], json!({ "pokemon": [999] }));
+
+        // only whoever created the set can change it
+        let resp = client.patch(&url).json(&json!({ "name": "Mine" })).dispatch().await;
+        assert_eq!(resp.status(), Status::Unauthorized);
+        let resp = client.patch(&url)
+            .header(bearer(&json!({ "token": uuid::Uuid::new_v4().to_string() })))
+            .json(&json!({ "name": "Mine" }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Forbidden);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "not_owner");
+        let debug_set = find_by_name(&client, "/api/v1/draft_set", "Debug Set").await;
+        let resp = client.delete(format!("/api/v1/draft_set/{debug_set}")).header(bearer(&owner)).dispatch().await;
+        assert_eq!(resp.status(), Status::Forbidden);
+
+        // a rejected update changes nothing
+        let resp = client.patch(&url)
+            .header(bearer(&owner))
+            .json(&json!({ "name": "Half Done", "add": [999], "remove": [6] }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::BadRequest);
+        let unchanged: Value = client.get(&url).dispatch().await.into_json().await.unwrap();
+        assert_eq!(unchanged["name"], "Fire and Water");
+        assert_eq!(unchanged["pokemon"]["Ids"], json!([4, 6, 9]));
+
+        let set: Value = client.patch(&url)
+            .header(bearer(&owner))
+            .json(&json!({ "name": "Starters", "add": [1, { "dex_id": 4, "tier": "OU" }], "remove": [6, 9] }))
+            .dispatch().await
+            .into_json().await.unwrap();
+        assert_eq!(set["name"], "Starters");
+        assert_eq!(set["members"], json!([{ "dex_id": 1 }, { "dex_id": 4, "tier": "OU" }]));
+
+        let set: Value = client.patch(&url)
+            .header(bearer(&owner))
+            .json(&json!({ "add_filter": { "types": ["WATER"], "base_only": true } }))
+            .dispatch().await
+            .into_json().await.unwrap();
+        assert_eq!(set["pokemon"]["Ids"], json!([1, 4, 7]));
+
+        // sessions can be made from it like any other set
+        let (session_id, _) = create_session_with_set(&client, "Starters", "Intergration Test Snake").await;
+        let session: Value = client.get(format!("/api/v1/draft_session/{session_id}")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(session["draft_pool"].as_array().unwrap().len(), 3);
+
+        assert_eq!(client.delete(&url).header(bearer(&owner)).dispatch().await.status(), Status::Ok);
+        assert_eq!(client.get(&url).dispatch().await.status(), Status::NotFound);
+        assert_eq!(client.delete(&url).header(bearer(&owner)).dispatch().await.status(), Status::NotFound);
+        let resp = client.patch(&url).header(bearer(&owner)).json(&json!({ "name": "Gone" })).dispatch().await;
+        assert_eq!(resp.status(), Status::NotFound);
+    }
+
+    #[rocket::async_test]
+    async fn test_pokemon_search() {
+        let client = client().await;
+        let search = |query: &str| client.get(format!("/api/v1/pokemon?{query}")).dispatch();
+        let dex_ids = |page: &Value| -> Vec<u64> {
+            page["pokemon"].as_array().unwrap().iter().map(|p| p["dex_id"].as_u64().unwrap()).collect()
+        };
+
+        let page: Value = search("limit=4").await.into_json().await.unwrap();
+        assert_eq!(page["total"], 9);
+        assert_eq!(dex_ids(&page), vec![1, 2, 3, 4]);
+        assert_eq!(page["next_offset"], 4);
+        let page: Value = search("limit=4&offset=8").await.into_json().await.unwrap();
+        assert_eq!(dex_ids(&page), vec![9]);
+        assert!(page.get("next_offset").is_none());
+
+        let page: Value = search("type=fire&sort=name&desc=true").await.into_json().await.unwrap();
+        // Charmeleon, Charmander, Charizard
+        assert_eq!(dex_ids(&page), vec![5, 4, 6]);
+        let page: Value = search("type1=FIRE&type2=NONE").await.into_json().await.unwrap();
+        assert_eq!(dex_ids(&page), vec![4, 5]);
+        let page: Value = search("base_only=true&gen=1&is_legendary=false").await.into_json().await.unwrap();
+        assert_eq!(dex_ids(&page), vec![1, 4, 7]);
+
+        let page: Value = search("name=char").await.into_json().await.unwrap();
+        assert_eq!(dex_ids(&page), vec![4, 5, 6]);
+        let page: Value = search("name=saur").await.into_json().await.unwrap();
+        assert_eq!(page["total"], 0);
+        // names starting with it come before names that just have it in them
+        let page: Value = search("name=s&fuzzy=true").await.into_json().await.unwrap();
+        assert_eq!(dex_ids(&page), vec![7, 1, 2, 3, 9]);
+        let page: Value = search("name=squirtl&fuzzy=true").await.into_json().await.unwrap();
+        assert_eq!(dex_ids(&page), vec![7]);
+        let page: Value = search("name=saur&fuzzy=true").await.into_json().await.unwrap();
+        assert_eq!(dex_ids(&page), vec![1, 2, 3]);
+        let page: Value = search("name=blastiose&fuzzy=true").await.into_json().await.unwrap();
+        assert_eq!(dex_ids(&page), vec![9]);
+    }
+
+    #[rocket::async_test]
+    async fn test_evolutions() {
+        let client = client().await;
+
+        let resp = client.get("/api/v1/pokemon/5/evolutions").dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let family: Value = resp.into_json().await.unwrap();
+        assert_eq!(family["base_form"], 4);
+        assert_eq!(family["pre_evolutions"], json!([4]));
+        assert_eq!(family["evolutions"], json!([6]));
+        assert_eq!(family["family"], json!([4, 5, 6]));
+        assert_eq!(family["tree"]["name"], "Charmander");
+        assert_eq!(family["tree"]["evolves_into"][0]["evolves_into"][0]["dex_id"], 6);
+
+        let resp = client.get("/api/v1/pokemon/151/evolutions").dispatch().await;
+        assert_eq!(resp.status(), Status::NotFound);
+        let body: Value = resp.into_json().await.unwrap();
+        assert_eq!(body["code"], "pokemon_not_found");
+    }
+
+    #[rocket::async_test]
+    async fn test_alternate_forms() {
+        let store = MemoryStore::from_seed_file("scripts/memory_seed.json").await.expect("seed file");
+        let mega: Pokemon = serde_json::from_value(json!({
+            "dex_id": 10034,
+            "name": "Charizard-Mega-X",
+            "type1": "FIRE",
+            "type2": "DRAGON",
+            "evolves_from": 6,
+            "gen": 6,
+            "is_legendary": false,
+            "is_mythic": false,
+            "stats": { "hp": 78, "atk": 130, "def": 111, "spa": 130, "spd": 85, "spe": 100 },
+            "abilities": [{ "name": "Tough Claws" }],
+            "form": { "species": 6, "kind": "Mega", "name": "mega-x" },
+        })).unwrap();
+        store.insert_pokemon(mega).await;
+        let client = Client::tracked(mount_api(rocket::build(), Arc::new(store))).await.expect("valid rocket");
+
+        let charizard: Value = client.get("/api/v1/pokemon/get/6").dispatch().await.into_json().await.unwrap();
+        assert_eq!(charizard["stats"]["bst"], 534);
+        assert_eq!(charizard["abilities"][1], json!({ "name": "Solar Power", "is_hidden": true }));
+        assert!(charizard.get("form").is_none());
+        let mega: Value = client.get("/api/v1/pokemon/get/10034").dispatch().await.into_json().await.unwrap();
+        assert_eq!(mega["form"]["species"], 6);
+        assert_eq!(mega["stats"]["bst"], 634);
+
+        let dex_ids = |page: &Value| -> Vec<u64> {
+            page["pokemon"].as_array().unwrap().iter().map(|p| p["dex_id"].as_u64().unwrap()).collect()
+        };
+        let page: Value = client.get("/api/v1/pokemon?name=chari").dispatch().await.into_json().await.unwrap();
+        assert_eq!(dex_ids(&page), vec![6, 10034]);
+        let page: Value = client.get("/api/v1/pokemon?forms=false").dispatch().await.into_json().await.unwrap();
+        assert_eq!(page["total"], 9);
+        let page: Value = client.get("/api/v1/pokemon?sort=bst&desc=true&limit=2").dispatch().await.into_json().await.unwrap();
+        assert_eq!(dex_ids(&page), vec![10034, 6]);
+
+        let family: Value = client.get("/api/v1/pokemon/10034/evolutions").dispatch().await.into_json().await.unwrap();
+        assert_eq!(family["family"], json!([4, 5, 6, 10034]));
+
+        // drafted apart from the species
+        let set: Value = client.post("/api/v1/draft_set/create")
+            .json(&json!({ "name": "Charizards", "pokemon": [6, 10034] }))
+            .dispatch().await
+            .into_json().await.unwrap();
+        assert_eq!(set["pokemon"]["Ids"], json!([6, 10034]));
+    }
+
+    async fn create_timed_rules(client: &Client) {
+        let resp = client.post("/api/v1/draft_rules/create")
+            .json(&json!({
+                "name": "Timed",
+                "picks_per_round": 1,
+                "bans_per_round": 0,
+                "max_pokemon": 1,
+                "starting_phase": "Pick",
+                "turn_type": "Snake",
+                "turn_time_limit": 30,
+                "on_timeout": "AutoSelect",
+            }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+    }
+
+    #[rocket::async_test]
+    async fn test_timer_auto_picks_for_idle_player() {
+        let client = client().await;
+        create_timed_rules(&client).await;
+
+        let (session_id, _, _) = start_two_player_draft(&client, "Debug Set", "Timed").await;
+        let url = |path: &str| format!("/api/v1/draft_session/{session_id}/{path}");
+
+        let update: Value = client.get(url("update")).dispatch().await.into_json().await.unwrap();
+        let deadline = update["turn_deadline"].as_u64().expect("turn deadline");
+
+        let store = client.rocket().state::<Store>().unwrap();
+        let hub = client.rocket().state::<EventHub>().unwrap();
+        let pokedex = client.rocket().state::<Arc<Pokedex>>().unwrap();
+        timer::expire_turns(store, hub, pokedex, deadline - 1).await;
+        let update: Value = client.get(url("update")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(update["current_player"], "Player 1");
+
+        timer::expire_turns(store, hub, pokedex, deadline).await;
+        let update: Value = client.get(url("update")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(update["current_player"], "Player 2");
+        let picked = update["players"][0]["pokemon"].as_array().unwrap();
+        assert_eq!(picked.len(), 1);
+        assert!((1..=9).contains(&picked[0].as_u64().unwrap()));
+    }
+
+    #[rocket::async_test]
+    async fn test_timer_drops_stale_timeout() {
+        let client = client().await;
+        create_timed_rules(&client).await;
+
+        let (session_id, p1, _) = start_two_player_draft(&client, "Debug Set", "Timed").await;
+        let url = |path: &str| format!("/api/v1/draft_session/{session_id}/{path}");
+
+        // the timer reads the session, then Player 1 picks before it writes
+        let store = client.rocket().state::<Store>().unwrap();
+        let hub = client.rocket().state::<EventHub>().unwrap();
+        let pokedex = client.rocket().state::<Arc<Pokedex>>().unwrap();
+        let stale = store.get_session(&session_id).await.unwrap().unwrap();
+        let deadline = stale.turn_deadline.expect("turn deadline");
+        assert_eq!(pick(&client, &session_id, &p1, 4).dispatch().await.status(), Status::Ok);
+
+        timer::time_out(store, hub, pokedex, &session_id, stale, deadline).await.unwrap();
+        let update: Value = client.get(url("update")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(update["players"][0]["pokemon"], json!([4]));
+        assert_eq!(update["players"][1]["pokemon"], json!([]));
+        assert_eq!(update["current_player"], "Player 2");
+
+        // and a request working from a session the timer has moved on from
+        let stale = store.get_session(&session_id).await.unwrap().unwrap();
+        timer::time_out(store, hub, pokedex, &session_id, stale.clone(), deadline + 60_000).await.unwrap();
+        let (after, _) = engine::apply(stale.clone(), engine::DraftAction::Cancel, deadline).unwrap();
+        assert!(!store.update_session_if(&after, stale.action_count).await.unwrap());
+    }
+
+    #[rocket::async_test]
+    async fn test_pick_queue() {
+        let client = client().await;
+        create_timed_rules(&client).await;
+
+        let (session_id, host) = create_session(&client, "Timed").await;
+        let url = |path: &str| format!("/api/v1/draft_session/{session_id}/{path}");
+        let p1 = join(&client, &session_id, "Player 1").await;
+        let p2 = join(&client, &session_id, "Player 2").await;
+
+        let resp = client.get(url("queue")).dispatch().await;
+        assert_eq!(resp.status(), Status::Unauthorized);
+
+        let resp = client.post(url("queue")).header(bearer(&p1)).json(&json!({ "pokemon": [5, 150] })).dispatch().await;
+        assert_eq!(resp.status(), Status::BadRequest);
+
+        let queue: Value = client.post(url("queue")).header(bearer(&p1))
+            .json(&json!({ "pokemon": [5, 3, 5, 7] }))
+            .dispatch().await
+            .into_json().await.unwrap();
+        assert_eq!(queue, json!({ "pokemon": [5, 3, 7] }));
+
+        let queue: Value = client.post(url("queue/move")).header(bearer(&p1))
+            .json(&json!({ "pokemon_id": 7, "position": 0 }))
+            .dispatch().await
+            .into_json().await.unwrap();
+        assert_eq!(queue, json!({ "pokemon": [7, 5, 3] }));
+
+        let resp = client.post(url("queue/move")).header(bearer(&p1))
+            .json(&json!({ "pokemon_id": 9, "position": 0 }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::NotFound);
+
+        client.post(url("queue")).header(bearer(&p2)).json(&json!({ "pokemon": [7, 2] })).dispatch().await;
+        let queue: Value = client.get(url("queue")).header(bearer(&p2)).dispatch().await.into_json().await.unwrap();
+        assert_eq!(queue, json!({ "pokemon": [7, 2] }));
+
+        for player in [&p1, &p2] {
+            client.post(url("ready")).header(ContentType::JSON).header(bearer(player)).dispatch().await;
+        }
+        client.post(url("start")).header(ContentType::JSON).header(bearer(&host)).dispatch().await;
+
+        // Player 1 sleeps through their turn and gets the top of their queue
+        let update: Value = client.get(url("update")).dispatch().await.into_json().await.unwrap();
+        let deadline = update["turn_deadline"].as_u64().expect("turn deadline");
+        let store = client.rocket().state::<Store>().unwrap();
+        let hub = client.rocket().state::<EventHub>().unwrap();
+        let pokedex = client.rocket().state::<Arc<Pokedex>>().unwrap();
+        timer::expire_turns(store, hub, pokedex, deadline).await;
+
+        let update: Value = client.get(url("update")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(update["players"][0]["pokemon"], json!([7]));
+
+        let queue: Value = client.get(url("queue")).header(bearer(&p1)).dispatch().await.into_json().await.unwrap();
+        assert_eq!(queue, json!({ "pokemon": [5, 3] }));
+        let queue: Value = client.get(url("queue")).header(bearer(&p2)).dispatch().await.into_json().await.unwrap();
+        assert_eq!(queue, json!({ "pokemon": [2] }));
+
+        // an action saving players it read before a pick and a queue change leaves both be
+        let before = store.get_session(&session_id).await.unwrap().unwrap().players.unwrap();
+        client.post(url("queue")).header(bearer(&p2)).json(&json!({ "pokemon": [3, 2] })).dispatch().await;
+        let resp = client.post(url("select-pokemon"))
+            .header(bearer(&p2))
+            .json(&json!({ "pokemon_id": 4, "action": "Pick" }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::Ok);
+        let mut after = before.clone();
+        after[1].ready = false;
+        store::save_players(store, &before, &after).await.unwrap();
+
+        let update: Value = client.get(url("update")).dispatch().await.into_json().await.unwrap();
+        assert_eq!(update["players"][1]["pokemon"], json!([4]));
+        let queue: Value = client.get(url("queue")).header(bearer(&p2)).dispatch().await.into_json().await.unwrap();
+        assert_eq!(queue, json!({ "pokemon": [3, 2] }));
+    }
+
+    #[rocket::async_test]
+    async fn test_errors_are_json() {
+        let client = client().await;
+
+        let resp = client.get("/api/v1/draft_session/missing/update").dispatch().await;
+        assert_eq!(resp.status(), Status::NotFound);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error, json!({
+            "code": "session_not_found",
+            "message": "Session not found",
+            "details": null,
+        }));
+
+        let rules_id = find_by_name(&client, "/api/v1/draft_rules", "Intergration Test Snake Pick First").await;
+        let resp = client.post("/api/v1/draft_session/create")
+            .json(&json!({
+                "name": "TEST",
+                "draft_set": "missing",
+                "draft_rules": rules_id,
+                "min_num_players": 2,
+                "max_num_players": 3,
+            }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::NotFound);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "draft_set_not_found");
+
+        let resp = client.post("/api/v1/draft_rules/create")
+            .json(&json!({
+                "name": "No turns",
+                "picks_per_round": 1,
+                "bans_per_round": 0,
+                "max_pokemon": 1,
+                "starting_phase": "Pick",
+                "turn_type": "Custom",
+            }))
+            .dispatch().await;
+        assert_eq!(resp.status(), Status::BadRequest);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "invalid_turn_order");
+
+        let resp = client.get("/api/v1/not-a-route").dispatch().await;
+        assert_eq!(resp.status(), Status::NotFound);
+        let error: Value = resp.into_json().await.unwrap();
+        assert_eq!(error["code"], "not_found");
+    }
 }
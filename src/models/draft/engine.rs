@@ -0,0 +1,1110 @@
+//! The draft rules, with no I/O. Routes load a [`DraftSession`] (with its
+//! players), run an action through [`apply`] and persist whatever comes back.
+//! Time comes in as `now`, unix milliseconds, so turn deadlines stay testable.
+use std::fmt;
+
+use super::order::{TurnSequence, TurnStep};
+use super::roster::{self, RosterViolation};
+use super::{DraftPhase, DraftSession, DraftState, DraftUser, HistoryEntry, Selection, TimeoutAction};
+use crate::events::DraftEvent;
+
+use serde::{Deserialize, Serialize};
+use surrealdb::RecordId;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DraftAction {
+    /// `user` must already carry the id it will be stored under.
+    Join { user: DraftUser },
+    ToggleReady { user_id: RecordId },
+    Start,
+    Select { user_id: RecordId, pokemon_id: u32, action: DraftPhase },
+    Pause,
+    Resume,
+    Cancel,
+    Kick { user_id: RecordId },
+    /// Every player in the session, in their new order.
+    Reorder { order: Vec<RecordId> },
+    SetAcceptingPlayers { accepting: bool },
+    /// The current turn ran out of time. `auto_select` is what to pick or ban
+    /// for the player when the rules say to, skipped if there's nothing.
+    TurnTimeout { auto_select: Option<u32> },
+    /// Takes back the latest pick or ban that hasn't been undone yet.
+    Undo,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleViolation {
+    NotAcceptingPlayers,
+    NameTaken,
+    SessionFull,
+    UserNotInSession,
+    NoPlayers,
+    DraftInProgress,
+    DraftNotStarted,
+    PokemonUnavailable,
+    ActionNotAllowed { current_phase: DraftPhase },
+    NotYourTurn,
+    DraftPaused,
+    DraftOver,
+    DraftAlreadyStarted,
+    InvalidTransition { from: DraftState, to: DraftState },
+    InvalidOrder,
+    NotEnoughPlayers { min: u16, joined: u32 },
+    PlayersNotReady,
+    TurnNotOver,
+    NotInDraftSet,
+    NothingToUndo,
+    InvalidTurnOrder,
+    OverBudget { cost: u32, remaining: u32 },
+    TierFull { tier: String, max_picks: u32 },
+    RosterConstraint(RosterViolation),
+}
+
+impl fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            RuleViolation::NotAcceptingPlayers => "Draft is no longer accepting players.",
+            RuleViolation::NameTaken => "Username already in use",
+            RuleViolation::SessionFull => "No slots available to join",
+            RuleViolation::UserNotInSession => "User not in session.",
+            RuleViolation::NoPlayers => "Can't ready when no players are in session",
+            RuleViolation::DraftInProgress => "Can't ready when the draft is in progress.",
+            RuleViolation::DraftNotStarted => "Draft has not yet started",
+            RuleViolation::PokemonUnavailable => {
+                "Pokemon cannot be selected. It's either banned or has already been selected."
+            },
+            RuleViolation::ActionNotAllowed { .. } => "Current action not allowed",
+            RuleViolation::NotYourTurn => "It is not your turn",
+            RuleViolation::DraftPaused => "The draft is paused",
+            RuleViolation::DraftOver => "The draft is over",
+            RuleViolation::DraftAlreadyStarted => "The draft has already started",
+            RuleViolation::InvalidTransition { from, to } => {
+                return write!(f, "Draft can't go from {from:?} to {to:?}");
+            },
+            RuleViolation::InvalidOrder => "The new order has to list every player in the session exactly once",
+            RuleViolation::NotEnoughPlayers { min, joined } => {
+                return write!(f, "Need at least {min} players to start, {joined} joined");
+            },
+            RuleViolation::PlayersNotReady => "Every player has to be ready before the draft starts",
+            RuleViolation::TurnNotOver => "The current turn still has time left",
+            RuleViolation::NotInDraftSet => "Pokemon is not part of this session's draft set",
+            RuleViolation::NothingToUndo => "There's no pick or ban to undo",
+            RuleViolation::InvalidTurnOrder => "The turn order has no turns, or turns for players who haven't joined",
+            RuleViolation::OverBudget { cost, remaining } => {
+                return write!(f, "That pokemon costs {cost} points and you only have {remaining} left");
+            },
+            RuleViolation::TierFull { tier, max_picks } => {
+                return write!(f, "You already have {max_picks} pokemon from tier {tier}");
+            },
+            RuleViolation::RosterConstraint(v) => return write!(f, "{v}"),
+        };
+        write!(f, "{msg}")
+    }
+}
+
+impl std::error::Error for RuleViolation {}
+
+impl DraftAction {
+    /// The action as it goes in the event log.
+    pub fn for_history(&self) -> DraftAction {
+        match self {
+            DraftAction::Join { user } => DraftAction::Join { user: user.without_secrets() },
+            other => other.clone(),
+        }
+    }
+}
+
+pub type EngineResult = Result<(DraftSession, Vec<DraftEvent>), RuleViolation>;
+
+pub fn apply(state: DraftSession, action: DraftAction, now: u64) -> EngineResult {
+    let (mut state, events) = apply_action(state, action, now)?;
+    state.action_count += 1;
+    Ok((state, events))
+}
+
+fn apply_action(state: DraftSession, action: DraftAction, now: u64) -> EngineResult {
+    match action {
+        DraftAction::Join { user } => join(state, user),
+        DraftAction::ToggleReady { user_id } => toggle_ready(state, &user_id),
+        DraftAction::Start => start(state, now),
+        DraftAction::Select { user_id, pokemon_id, action } => select(state, &user_id, pokemon_id, action, now),
+        DraftAction::Pause => pause(state),
+        DraftAction::Resume => resume(state, now),
+        DraftAction::Cancel => cancel(state),
+        DraftAction::Kick { user_id } => kick(state, &user_id),
+        DraftAction::Reorder { order } => reorder(state, order),
+        DraftAction::SetAcceptingPlayers { accepting } => set_accepting_players(state, accepting),
+        DraftAction::TurnTimeout { auto_select } => turn_timeout(state, auto_select, now),
+        DraftAction::Undo => undo(state, now),
+    }
+}
+
+/// Every change to `draft_state` goes through here.
+fn move_to(state: &mut DraftSession, to: DraftState) -> Result<(), RuleViolation> {
+    if !state.draft_state.can_transition_to(to) {
+        return Err(RuleViolation::InvalidTransition { from: state.draft_state, to });
+    }
+
+    state.draft_state = to;
+    Ok(())
+}
+
+/// Ready once there are enough players and all of them are ready, Open otherwise.
+fn update_ready_state(state: &mut DraftSession) -> Result<(), RuleViolation> {
+    let players = state.players.as_deref().unwrap_or_default();
+    let enough = !players.is_empty() && players.len() >= state.min_num_players as usize;
+    let to = if enough && players.iter().all(|p| p.ready) {
+        DraftState::Ready
+    } else {
+        DraftState::Open
+    };
+
+    if state.draft_state != to {
+        move_to(state, to)?;
+    }
+    Ok(())
+}
+
+fn join(mut state: DraftSession, mut user: DraftUser) -> EngineResult {
+    check_roster_open(&state)?;
+    let num_of_players = state.num_of_players();
+
+    // a full session stops accepting players too, so this goes first
+    if num_of_players >= (state.max_num_players as u32) {
+        return Err(RuleViolation::SessionFull);
+    }
+    if !state.accepting_players {
+        return Err(RuleViolation::NotAcceptingPlayers);
+    }
+    if state.is_name_taken(&user.name) {
+        return Err(RuleViolation::NameTaken);
+    }
+
+    user.order_in_session = num_of_players;
+    user.ready = false;
+
+    if num_of_players == 0 {
+        state.current_player = user.id.clone();
+    }
+    state.accepting_players = num_of_players + 1 < (state.max_num_players as u32);
+
+    let event = DraftEvent::PlayerJoined {
+        player: user.name.clone(),
+        order_in_session: user.order_in_session,
+    };
+    state.players.get_or_insert_with(Vec::new).push(user);
+    // a new player is never ready, so neither is the session
+    update_ready_state(&mut state)?;
+
+    Ok((state, vec![event]))
+}
+
+fn toggle_ready(mut state: DraftSession, user_id: &RecordId) -> EngineResult {
+    match state.draft_state {
+        DraftState::Open | DraftState::Ready => (),
+        DraftState::InProgress | DraftState::Paused => return Err(RuleViolation::DraftInProgress),
+        DraftState::Ended | DraftState::Cancelled => return Err(RuleViolation::DraftOver),
+    }
+
+    let players = match state.players.as_mut() {
+        Some(p) => p,
+        None => return Err(RuleViolation::NoPlayers),
+    };
+    let user = match players.iter_mut().find(|p| p.id.as_ref() == Some(user_id)) {
+        Some(u) => u,
+        None => return Err(RuleViolation::UserNotInSession),
+    };
+
+    user.ready = !user.ready;
+    let (player, ready) = (user.name.clone(), user.ready);
+
+    update_ready_state(&mut state)?;
+
+    let draft_state = state.draft_state;
+    Ok((state, vec![DraftEvent::ReadyToggled { player, ready, draft_state }]))
+}
+
+fn start(mut state: DraftSession, now: u64) -> EngineResult {
+    check_roster_open(&state)?;
+
+    let joined = state.num_of_players();
+    if joined == 0 || joined < state.min_num_players as u32 {
+        return Err(RuleViolation::NotEnoughPlayers { min: state.min_num_players, joined });
+    }
+    if state.draft_state != DraftState::Ready {
+        return Err(RuleViolation::PlayersNotReady);
+    }
+
+    // every slot the order names has to have someone in it
+    let seated = TurnSequence::new(&state.draft_rules, joined, state.order_seed).all(|step| step.slot < joined);
+    let first = match step_at(&state, 0) {
+        Some(step) if seated => step,
+        _ => return Err(RuleViolation::InvalidTurnOrder),
+    };
+
+    move_to(&mut state, DraftState::InProgress)?;
+    state.accepting_players = false;
+    state.turn_ticker = 0;
+    state.current_player = player_in_slot(&state, first.slot);
+    state.current_phase = first.action;
+    start_turn(&mut state, now);
+    Ok((state, vec![DraftEvent::DraftStarted]))
+}
+
+fn check_in_progress(state: &DraftSession) -> Result<(), RuleViolation> {
+    match state.draft_state {
+        DraftState::InProgress => Ok(()),
+        DraftState::Paused => Err(RuleViolation::DraftPaused),
+        DraftState::Ended | DraftState::Cancelled => Err(RuleViolation::DraftOver),
+        DraftState::Open | DraftState::Ready => Err(RuleViolation::DraftNotStarted),
+    }
+}
+
+fn select(
+    mut state: DraftSession,
+    user_id: &RecordId,
+    pokemon_id: u32,
+    action: DraftPhase,
+    now: u64,
+) -> EngineResult {
+    check_in_progress(&state)?;
+    if !state.is_in_draft_pool(&pokemon_id) {
+        return Err(RuleViolation::NotInDraftSet);
+    }
+    if state.is_pokemon_chosen(&pokemon_id) {
+        return Err(RuleViolation::PokemonUnavailable);
+    }
+    if action != state.current_phase {
+        return Err(RuleViolation::ActionNotAllowed { current_phase: state.current_phase });
+    }
+    if !state.is_current_player(user_id) {
+        return Err(RuleViolation::NotYourTurn);
+    }
+    if action == DraftPhase::Pick {
+        check_roster(&state, user_id, pokemon_id)?;
+    }
+
+    let player = match state.get_player_mut(user_id) {
+        Some(p) => p,
+        None => return Err(RuleViolation::UserNotInSession),
+    };
+    if action == DraftPhase::Pick {
+        player.selected_pokemon.push(pokemon_id);
+    }
+
+    let name = player.name.clone();
+    let mut events = vec![match action {
+        DraftPhase::Pick => DraftEvent::PokemonPicked { player: name, pokemon_id },
+        DraftPhase::Ban => DraftEvent::PokemonBanned { player: name, pokemon_id },
+    }];
+    let selection = Selection { pokemon_id, user_id: user_id.clone() };
+    match action {
+        DraftPhase::Pick => state.picked.push(selection),
+        DraftPhase::Ban => state.banned.push(selection),
+    }
+    for player in state.players.iter_mut().flatten() {
+        player.drop_from_pick_queue(pokemon_id);
+    }
+    state.history.push(HistoryEntry::Selection {
+        user_id: user_id.clone(),
+        pokemon_id,
+        action,
+        turn: state.turn_ticker,
+        undone: false,
+    });
+
+    events.extend(advance_turn(&mut state, now)?);
+    Ok((state, events))
+}
+
+/// What a pick has to get past on top of being the player's turn and the
+/// pokemon being free: the point budget, the tier limits and the roster
+/// constraints.
+pub fn check_roster(state: &DraftSession, user_id: &RecordId, pokemon_id: u32) -> Result<(), RuleViolation> {
+    if let Some(remaining) = state.points_remaining(user_id) {
+        let cost = state.points_of(&pokemon_id);
+        if cost > remaining {
+            return Err(RuleViolation::OverBudget { cost, remaining });
+        }
+    }
+
+    if let Some(tier) = state.tier_of(&pokemon_id) {
+        let limit = state.draft_rules.tier_limits.iter().find(|l| l.tier == tier);
+        if let Some(limit) = limit.filter(|l| state.picks_in_tier(user_id, tier) >= l.max_picks) {
+            return Err(RuleViolation::TierFull { tier: tier.to_string(), max_picks: limit.max_picks });
+        }
+    }
+
+    if state.has_roster_constraints() {
+        let team: Vec<u32> = state.picked.iter().filter(|s| s.user_id == *user_id).map(|s| s.pokemon_id).collect();
+        let picks_left = picks_for(state, user_id).saturating_sub(team.len() as u32 + 1);
+        roster::check(&state.draft_rules.roster_constraints, &state.pokedex, &team, pokemon_id, picks_left)
+            .map_err(RuleViolation::RosterConstraint)?;
+    }
+
+    Ok(())
+}
+
+/// How many picks the turn order gives `user_id` over the whole draft.
+fn picks_for(state: &DraftSession, user_id: &RecordId) -> u32 {
+    let slot = match state.get_player(user_id) {
+        Some(p) => p.order_in_session,
+        None => return 0,
+    };
+    TurnSequence::new(&state.draft_rules, state.num_of_players(), state.order_seed)
+        .filter(|step| step.slot == slot && step.action == DraftPhase::Pick)
+        .count() as u32
+}
+
+/// Hands the turn to the next step of the turn order, ending the draft after
+/// the last one.
+fn advance_turn(state: &mut DraftSession, now: u64) -> Result<Vec<DraftEvent>, RuleViolation> {
+    state.turn_ticker += 1;
+
+    let step = match step_at(state, state.turn_ticker) {
+        Some(s) => s,
+        None => {
+            move_to(state, DraftState::Ended)?;
+            state.current_player = None;
+            state.turn_deadline = None;
+            return Ok(vec![DraftEvent::DraftEnded]);
+        },
+    };
+
+    let mut events = vec![];
+    if step.action != state.current_phase {
+        events.push(DraftEvent::PhaseChanged { phase: step.action });
+    }
+    state.current_player = player_in_slot(state, step.slot);
+    state.current_phase = step.action;
+    start_turn(state, now);
+
+    Ok(events)
+}
+
+/// Starts the clock on the current turn, if the rules have one.
+fn start_turn(state: &mut DraftSession, now: u64) {
+    state.turn_deadline = state
+        .draft_rules
+        .turn_time_limit
+        .map(|secs| now + u64::from(secs) * 1000);
+}
+
+fn turn_timeout(mut state: DraftSession, auto_select: Option<u32>, now: u64) -> EngineResult {
+    check_in_progress(&state)?;
+    match state.turn_deadline {
+        Some(deadline) if now >= deadline => (),
+        _ => return Err(RuleViolation::TurnNotOver),
+    }
+
+    let user_id = match state.current_player.clone() {
+        Some(u) => u,
+        None => return Err(RuleViolation::NoPlayers),
+    };
+    let player = match state.get_player(&user_id) {
+        Some(p) => p.name.clone(),
+        None => return Err(RuleViolation::UserNotInSession),
+    };
+    let mut events = vec![DraftEvent::TurnTimedOut { player }];
+
+    match (state.draft_rules.on_timeout, auto_select) {
+        (TimeoutAction::AutoSelect, Some(pokemon_id)) => {
+            let phase = state.current_phase;
+            let (state, selected) = select(state, &user_id, pokemon_id, phase, now)?;
+            events.extend(selected);
+            Ok((state, events))
+        },
+        _ => {
+            events.extend(advance_turn(&mut state, now)?);
+            Ok((state, events))
+        },
+    }
+}
+
+/// Puts the session back on the turn of the latest pick or ban and takes the
+/// pokemon back out. Works on an ended draft too, so a misclick on the last
+/// pick can be fixed.
+fn undo(mut state: DraftSession, now: u64) -> EngineResult {
+    match state.draft_state {
+        DraftState::InProgress | DraftState::Paused | DraftState::Ended => (),
+        DraftState::Cancelled => return Err(RuleViolation::DraftOver),
+        DraftState::Open | DraftState::Ready => return Err(RuleViolation::DraftNotStarted),
+    }
+
+    let latest = state.history.iter_mut().rev().find_map(|entry| match entry {
+        HistoryEntry::Selection { user_id, pokemon_id, action, turn, undone } if !*undone => {
+            *undone = true;
+            Some((user_id.clone(), *pokemon_id, *action, *turn))
+        },
+        _ => None,
+    });
+    let (user_id, pokemon_id, action, turn) = match latest {
+        Some(s) => s,
+        None => return Err(RuleViolation::NothingToUndo),
+    };
+
+    let player = match state.get_player_mut(&user_id) {
+        Some(p) => p,
+        None => return Err(RuleViolation::UserNotInSession),
+    };
+    if action == DraftPhase::Pick {
+        player.selected_pokemon.retain(|pk| *pk != pokemon_id);
+    }
+    let name = player.name.clone();
+    match action {
+        DraftPhase::Pick => state.picked.retain(|s| s.pokemon_id != pokemon_id),
+        DraftPhase::Ban => state.banned.retain(|s| s.pokemon_id != pokemon_id),
+    }
+
+    let mut events = vec![DraftEvent::SelectionUndone { player: name, pokemon_id, action }];
+    if state.current_phase != action {
+        events.push(DraftEvent::PhaseChanged { phase: action });
+    }
+    state.current_player = Some(user_id.clone());
+    state.current_phase = action;
+    state.turn_ticker = turn;
+    state.history.push(HistoryEntry::Undo { user_id, pokemon_id, action, turn });
+
+    match state.draft_state {
+        DraftState::Ended => {
+            move_to(&mut state, DraftState::InProgress)?;
+            start_turn(&mut state, now);
+        },
+        DraftState::InProgress => start_turn(&mut state, now),
+        _ => (),
+    }
+
+    Ok((state, events))
+}
+
+fn pause(mut state: DraftSession) -> EngineResult {
+    move_to(&mut state, DraftState::Paused)?;
+    state.turn_deadline = None;
+    Ok((state, vec![DraftEvent::DraftPaused]))
+}
+
+/// The player whose turn it was gets a fresh clock.
+fn resume(mut state: DraftSession, now: u64) -> EngineResult {
+    // Ended -> InProgress is only for undo
+    if state.draft_state == DraftState::Ended {
+        return Err(RuleViolation::DraftOver);
+    }
+    move_to(&mut state, DraftState::InProgress)?;
+    start_turn(&mut state, now);
+    Ok((state, vec![DraftEvent::DraftResumed]))
+}
+
+fn cancel(mut state: DraftSession) -> EngineResult {
+    move_to(&mut state, DraftState::Cancelled)?;
+    state.accepting_players = false;
+    state.turn_deadline = None;
+    Ok((state, vec![DraftEvent::DraftCancelled]))
+}
+
+/// The roster can only change before the draft starts.
+fn check_roster_open(state: &DraftSession) -> Result<(), RuleViolation> {
+    match state.draft_state {
+        DraftState::Open | DraftState::Ready => Ok(()),
+        DraftState::Ended | DraftState::Cancelled => Err(RuleViolation::DraftOver),
+        DraftState::InProgress | DraftState::Paused => Err(RuleViolation::DraftAlreadyStarted),
+    }
+}
+
+/// Gives players their place in `players` and hands the first turn to whoever is first.
+fn renumber(state: &mut DraftSession, mut players: Vec<DraftUser>) -> Result<(), RuleViolation> {
+    for (i, player) in players.iter_mut().enumerate() {
+        player.order_in_session = i as u32;
+    }
+
+    state.current_player = players.first().and_then(|p| p.id.clone());
+    state.players = Some(players);
+    update_ready_state(state)
+}
+
+fn kick(mut state: DraftSession, user_id: &RecordId) -> EngineResult {
+    check_roster_open(&state)?;
+
+    let mut players = state.players.take().unwrap_or_default();
+    let kicked = match players.iter().position(|p| p.id.as_ref() == Some(user_id)) {
+        Some(i) => players.remove(i),
+        None => return Err(RuleViolation::UserNotInSession),
+    };
+    renumber(&mut state, players)?;
+
+    Ok((state, vec![DraftEvent::PlayerKicked { player: kicked.name }]))
+}
+
+fn reorder(mut state: DraftSession, order: Vec<RecordId>) -> EngineResult {
+    check_roster_open(&state)?;
+
+    let mut players = state.players.take().unwrap_or_default();
+    if order.len() != players.len() {
+        return Err(RuleViolation::InvalidOrder);
+    }
+
+    let mut reordered = Vec::with_capacity(players.len());
+    for id in &order {
+        match players.iter().position(|p| p.id.as_ref() == Some(id)) {
+            Some(i) => reordered.push(players.swap_remove(i)),
+            None => return Err(RuleViolation::InvalidOrder),
+        }
+    }
+    let names = reordered.iter().map(|p| p.name.clone()).collect();
+    renumber(&mut state, reordered)?;
+
+    Ok((state, vec![DraftEvent::PlayersReordered { players: names }]))
+}
+
+fn set_accepting_players(mut state: DraftSession, accepting: bool) -> EngineResult {
+    check_roster_open(&state)?;
+    if accepting && state.num_of_players() >= (state.max_num_players as u32) {
+        return Err(RuleViolation::SessionFull);
+    }
+
+    state.accepting_players = accepting;
+    Ok((state, vec![DraftEvent::AcceptingPlayersChanged { accepting_players: accepting }]))
+}
+
+/// The step the draft is on at `turn`, `None` once it's past the last one.
+fn step_at(state: &DraftSession, turn: u32) -> Option<TurnStep> {
+    TurnSequence::new(&state.draft_rules, state.num_of_players(), state.order_seed).nth(turn as usize)
+}
+
+fn player_in_slot(state: &DraftSession, slot: u32) -> Option<RecordId> {
+    state.players.as_deref()?
+        .iter()
+        .find(|p| p.order_in_session == slot)
+        .and_then(|p| p.id.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::draft::order::TurnStep;
+    use crate::models::draft::{DraftRules, TierLimit, TurnType};
+    use crate::models::pokemon::DraftSetMember;
+    use DraftState::*;
+
+    fn generate_players(size: u32) -> Vec<DraftUser>{
+        let mut players: Vec<DraftUser> = vec![];
+        for i in 0..size {
+            players.push(DraftUser {
+                id: Some(user_id(i)),
+                name: format!("Player {}", i + 1),
+                order_in_session: i,
+                ..Default::default()
+            })
+        }
+        players
+    }
+
+    fn user_id(i: u32) -> RecordId {
+        RecordId::from_table_key("draft_user", format!("p{i}"))
+    }
+
+    fn started_session(size: u32, rules: DraftRules) -> DraftSession {
+        DraftSession {
+            players: Some(generate_players(size)),
+            current_player: Some(user_id(0)),
+            current_phase: rules.starting_phase,
+            draft_rules: rules,
+            draft_state: DraftState::InProgress,
+            ..Default::default()
+        }
+    }
+
+    fn ids(selections: &[Selection]) -> Vec<u32> {
+        selections.iter().map(|s| s.pokemon_id).collect()
+    }
+
+    fn select_as(session: DraftSession, i: u32, pokemon_id: u32, action: DraftPhase) -> EngineResult {
+        apply(session, DraftAction::Select { user_id: user_id(i), pokemon_id, action }, 0)
+    }
+
+    #[test]
+    fn test_snake_order_and_phase_change() {
+        let session = started_session(3, DraftRules::default());
+
+        // bans go 1, 2, 3 then picks snake back 3, 2, 1
+        let (session, _) = select_as(session, 0, 1, DraftPhase::Ban).unwrap();
+        let (session, _) = select_as(session, 1, 2, DraftPhase::Ban).unwrap();
+        let (session, events) = select_as(session, 2, 3, DraftPhase::Ban).unwrap();
+        assert_eq!(events, vec![
+            DraftEvent::PokemonBanned { player: "Player 3".into(), pokemon_id: 3 },
+            DraftEvent::PhaseChanged { phase: DraftPhase::Pick },
+        ]);
+        assert_eq!(session.current_player, Some(user_id(2)));
+
+        let (session, _) = select_as(session, 2, 4, DraftPhase::Pick).unwrap();
+        let (session, _) = select_as(session, 1, 5, DraftPhase::Pick).unwrap();
+        let (session, events) = select_as(session, 0, 6, DraftPhase::Pick).unwrap();
+
+        assert_eq!(session.draft_state, DraftState::Ended);
+        assert_eq!(events.last(), Some(&DraftEvent::DraftEnded));
+        assert_eq!(session.current_player, None);
+        assert_eq!(ids(&session.banned), vec![1, 2, 3]);
+        assert_eq!(ids(&session.picked), vec![4, 5, 6]);
+        assert_eq!(session.picked[0], Selection { pokemon_id: 4, user_id: user_id(2) });
+        let rosters: Vec<Vec<u32>> = session.players.unwrap().into_iter().map(|p| p.selected_pokemon).collect();
+        assert_eq!(rosters, vec![vec![6], vec![5], vec![4]]);
+    }
+
+    #[test]
+    fn test_round_robin_order() {
+        let rules = DraftRules {
+            turn_type: TurnType::RoundRobin,
+            ..Default::default()
+        };
+        let session = started_session(2, rules);
+
+        let (session, _) = select_as(session, 0, 1, DraftPhase::Ban).unwrap();
+        let (session, _) = select_as(session, 1, 2, DraftPhase::Ban).unwrap();
+        assert_eq!(session.current_player, Some(user_id(0)));
+        assert_eq!(session.current_phase, DraftPhase::Pick);
+    }
+
+    #[test]
+    fn test_custom_turn_order() {
+        let rules = DraftRules {
+            turn_type: TurnType::Custom,
+            sequence: vec![
+                TurnStep { slot: 1, action: DraftPhase::Pick },
+                TurnStep { slot: 1, action: DraftPhase::Ban },
+                TurnStep { slot: 0, action: DraftPhase::Pick },
+            ],
+            ..Default::default()
+        };
+        let mut players = generate_players(2);
+        players.iter_mut().for_each(|p| p.ready = true);
+        let session = DraftSession {
+            players: Some(players),
+            min_num_players: 2,
+            draft_state: Ready,
+            draft_rules: rules,
+            ..Default::default()
+        };
+
+        let (session, _) = apply(session, DraftAction::Start, 0).unwrap();
+        assert_eq!(session.current_player, Some(user_id(1)));
+        assert_eq!(session.current_phase, DraftPhase::Pick);
+
+        let (session, _) = select_as(session, 1, 1, DraftPhase::Pick).unwrap();
+        assert_eq!(session.current_player, Some(user_id(1)));
+        let (session, events) = select_as(session, 1, 2, DraftPhase::Ban).unwrap();
+        assert_eq!(events[1], DraftEvent::PhaseChanged { phase: DraftPhase::Pick });
+        assert_eq!(session.current_player, Some(user_id(0)));
+        let (session, _) = select_as(session, 0, 3, DraftPhase::Pick).unwrap();
+        assert_eq!(session.draft_state, Ended);
+    }
+
+    #[test]
+    fn test_turn_order_needs_every_slot_filled() {
+        let rules = DraftRules {
+            turn_type: TurnType::Custom,
+            sequence: vec![TurnStep { slot: 2, action: DraftPhase::Pick }],
+            ..Default::default()
+        };
+        let mut players = generate_players(2);
+        players.iter_mut().for_each(|p| p.ready = true);
+        let session = DraftSession {
+            players: Some(players),
+            min_num_players: 2,
+            draft_state: Ready,
+            draft_rules: rules,
+            ..Default::default()
+        };
+
+        assert_eq!(apply(session, DraftAction::Start, 0).unwrap_err(), RuleViolation::InvalidTurnOrder);
+    }
+
+    #[test]
+    fn test_select_violations() {
+        let session = started_session(2, DraftRules::default());
+
+        let not_started = DraftSession { draft_state: DraftState::Open, ..session.clone() };
+        assert_eq!(select_as(not_started, 0, 1, DraftPhase::Ban).unwrap_err(), RuleViolation::DraftNotStarted);
+        assert_eq!(
+            select_as(session.clone(), 0, 1, DraftPhase::Pick).unwrap_err(),
+            RuleViolation::ActionNotAllowed { current_phase: DraftPhase::Ban }
+        );
+        assert_eq!(select_as(session.clone(), 1, 1, DraftPhase::Ban).unwrap_err(), RuleViolation::NotYourTurn);
+
+        let (session, _) = select_as(session, 0, 1, DraftPhase::Ban).unwrap();
+        assert_eq!(select_as(session.clone(), 1, 1, DraftPhase::Ban).unwrap_err(), RuleViolation::PokemonUnavailable);
+
+        let pool = [1, 2, 3].into_iter().map(DraftSetMember::new).collect();
+        let session = DraftSession { draft_pool: Some(pool), ..session };
+        assert_eq!(select_as(session.clone(), 1, 9, DraftPhase::Ban).unwrap_err(), RuleViolation::NotInDraftSet);
+        assert!(select_as(session, 1, 2, DraftPhase::Ban).is_ok());
+    }
+
+    #[test]
+    fn test_point_budget() {
+        let rules = DraftRules {
+            starting_phase: DraftPhase::Pick,
+            max_pokemon: 2,
+            point_budget: Some(10),
+            ..Default::default()
+        };
+        let mut session = started_session(2, rules);
+        session.draft_pool = Some(vec![
+            DraftSetMember { points: Some(8), ..DraftSetMember::new(1) },
+            DraftSetMember { points: Some(3), ..DraftSetMember::new(2) },
+            DraftSetMember { points: Some(2), ..DraftSetMember::new(3) },
+            DraftSetMember::new(4),
+            DraftSetMember { points: Some(20), ..DraftSetMember::new(5) },
+            DraftSetMember { points: Some(20), ..DraftSetMember::new(6) },
+        ]);
+        assert_eq!(session.points_remaining(&user_id(0)), Some(10));
+
+        let (session, _) = select_as(session, 0, 1, DraftPhase::Pick).unwrap();
+        assert_eq!(session.points_remaining(&user_id(0)), Some(2));
+        assert_eq!(session.points_remaining(&user_id(1)), Some(10));
+
+        let (session, _) = select_as(session, 1, 2, DraftPhase::Pick).unwrap();
+        assert_eq!(session.points_remaining(&user_id(1)), Some(7));
+
+        // bans are free
+        let (session, _) = select_as(session, 1, 5, DraftPhase::Ban).unwrap();
+        let (session, _) = select_as(session, 0, 6, DraftPhase::Ban).unwrap();
+        assert_eq!(session.points_remaining(&user_id(0)), Some(2));
+        assert!(select_as(session.clone(), 0, 3, DraftPhase::Pick).is_ok());
+
+        let mut over = session.clone();
+        over.draft_pool.as_mut().unwrap()[2].points = Some(3);
+        assert_eq!(
+            select_as(over, 0, 3, DraftPhase::Pick).unwrap_err(),
+            RuleViolation::OverBudget { cost: 3, remaining: 2 }
+        );
+
+        // nothing on the set means no cost
+        let (session, _) = select_as(session, 0, 4, DraftPhase::Pick).unwrap();
+        assert_eq!(session.points_remaining(&user_id(0)), Some(2));
+        assert_eq!(DraftSession::default().points_remaining(&user_id(0)), None);
+    }
+
+    #[test]
+    fn test_tier_limits() {
+        let rules = DraftRules {
+            starting_phase: DraftPhase::Pick,
+            bans_per_round: 0,
+            max_pokemon: 3,
+            tier_limits: vec![TierLimit { tier: "OU".into(), max_picks: 1 }],
+            ..Default::default()
+        };
+        let tier = |dex_id, tier: &str| DraftSetMember { tier: Some(tier.into()), ..DraftSetMember::new(dex_id) };
+        let mut session = started_session(1, rules);
+        session.draft_pool = Some(vec![tier(1, "OU"), tier(2, "OU"), tier(3, "UU"), tier(4, "UU"), DraftSetMember::new(5)]);
+
+        let (session, _) = select_as(session, 0, 1, DraftPhase::Pick).unwrap();
+        assert_eq!(session.picks_in_tier(&user_id(0), "OU"), 1);
+        assert_eq!(
+            select_as(session.clone(), 0, 2, DraftPhase::Pick).unwrap_err(),
+            RuleViolation::TierFull { tier: "OU".into(), max_picks: 1 }
+        );
+        assert!(!session.can_pick(&user_id(0), &2));
+
+        // no limit on UU, and untiered pokemon are never capped
+        let (session, _) = select_as(session, 0, 3, DraftPhase::Pick).unwrap();
+        let (session, _) = select_as(session, 0, 4, DraftPhase::Pick).unwrap();
+        assert_eq!(ids(&session.picked), vec![1, 3, 4]);
+        assert!(session.can_pick(&user_id(0), &5));
+    }
+
+    #[test]
+    fn test_undo() {
+        let rules = DraftRules {
+            turn_time_limit: Some(30),
+            ..Default::default()
+        };
+        let session = started_session(2, rules);
+        assert_eq!(apply(session.clone(), DraftAction::Undo, 0).unwrap_err(), RuleViolation::NothingToUndo);
+
+        let (session, _) = select_as(session, 0, 1, DraftPhase::Ban).unwrap();
+        let (mut session, _) = select_as(session, 1, 2, DraftPhase::Ban).unwrap();
+        session.draft_state = Ended;
+        assert_eq!(apply(session.clone(), DraftAction::Resume, 0).unwrap_err(), RuleViolation::DraftOver);
+
+        // a misclick on the last pick ended the draft, undoing it opens it back up
+        let (session, events) = apply(session, DraftAction::Undo, 5_000).unwrap();
+        assert_eq!(events[0], DraftEvent::SelectionUndone {
+            player: "Player 2".into(),
+            pokemon_id: 2,
+            action: DraftPhase::Ban,
+        });
+        assert_eq!(session.draft_state, InProgress);
+        assert_eq!(session.current_player, Some(user_id(1)));
+        assert_eq!(session.current_phase, DraftPhase::Ban);
+        assert_eq!(session.turn_ticker, 1);
+        assert_eq!(session.turn_deadline, Some(35_000));
+        assert_eq!(ids(&session.banned), vec![1]);
+
+        let (session, _) = apply(session, DraftAction::Undo, 0).unwrap();
+        assert_eq!(session.current_player, Some(user_id(0)));
+        assert_eq!(session.turn_ticker, 0);
+        assert!(session.banned.is_empty());
+        assert_eq!(session.history.len(), 4);
+        assert_eq!(apply(session.clone(), DraftAction::Undo, 0).unwrap_err(), RuleViolation::NothingToUndo);
+
+        let (session, _) = select_as(session, 0, 2, DraftPhase::Ban).unwrap();
+        assert_eq!(ids(&session.banned), vec![2]);
+        assert_eq!(session.current_player, Some(user_id(1)));
+
+        let cancelled = DraftSession { draft_state: Cancelled, ..session };
+        assert_eq!(apply(cancelled, DraftAction::Undo, 0).unwrap_err(), RuleViolation::DraftOver);
+    }
+
+    #[test]
+    fn test_undo_returns_picks() {
+        let rules = DraftRules { starting_phase: DraftPhase::Pick, ..Default::default() };
+        let session = started_session(2, rules);
+
+        let (session, _) = select_as(session, 0, 3, DraftPhase::Pick).unwrap();
+        assert_eq!(session.get_player(&user_id(0)).unwrap().selected_pokemon, vec![3]);
+
+        let (session, _) = apply(session, DraftAction::Undo, 0).unwrap();
+        assert!(session.get_player(&user_id(0)).unwrap().selected_pokemon.is_empty());
+        assert_eq!(session.current_phase, DraftPhase::Pick);
+    }
+
+    #[test]
+    fn test_selections_leave_pick_queues() {
+        let mut session = started_session(2, DraftRules::default());
+        for player in session.players.iter_mut().flatten() {
+            player.set_pick_queue(vec![4, 1, 4, 2]);
+        }
+        assert_eq!(session.next_queued(&user_id(1)), Some(4));
+
+        let (mut session, _) = select_as(session, 0, 4, DraftPhase::Ban).unwrap();
+        for player in session.players.iter().flatten() {
+            assert_eq!(player.pick_queue(), [1, 2]);
+        }
+
+        let player = session.get_player_mut(&user_id(0)).unwrap();
+        assert!(player.move_in_pick_queue(2, 0));
+        assert!(!player.move_in_pick_queue(4, 0));
+        assert_eq!(player.pick_queue(), [2, 1]);
+        assert_eq!(session.next_queued(&user_id(0)), Some(2));
+    }
+
+    #[test]
+    fn test_turn_timeout() {
+        let rules = DraftRules {
+            turn_time_limit: Some(30),
+            ..Default::default()
+        };
+        let mut players = generate_players(2);
+        players.iter_mut().for_each(|p| p.ready = true);
+        let session = DraftSession {
+            players: Some(players),
+            min_num_players: 2,
+            current_player: Some(user_id(0)),
+            draft_state: Ready,
+            draft_rules: rules,
+            ..Default::default()
+        };
+
+        let (session, _) = apply(session, DraftAction::Start, 1_000).unwrap();
+        assert_eq!(session.turn_deadline, Some(31_000));
+        let timeout = DraftAction::TurnTimeout { auto_select: Some(7) };
+        assert_eq!(apply(session.clone(), timeout.clone(), 30_999).unwrap_err(), RuleViolation::TurnNotOver);
+
+        // skipping ignores whatever was offered
+        let (skipped, events) = apply(session.clone(), timeout.clone(), 31_000).unwrap();
+        assert_eq!(events, vec![DraftEvent::TurnTimedOut { player: "Player 1".into() }]);
+        assert_eq!(skipped.current_player, Some(user_id(1)));
+        assert_eq!(skipped.turn_ticker, 1);
+        assert!(skipped.banned.is_empty() && skipped.picked.is_empty());
+        assert_eq!(skipped.turn_deadline, Some(61_000));
+
+        let mut session = session;
+        session.draft_rules.on_timeout = TimeoutAction::AutoSelect;
+        let (picked, events) = apply(session.clone(), timeout, 31_000).unwrap();
+        assert_eq!(events, vec![
+            DraftEvent::TurnTimedOut { player: "Player 1".into() },
+            DraftEvent::PokemonBanned { player: "Player 1".into(), pokemon_id: 7 },
+        ]);
+        assert_eq!(picked.banned, vec![Selection { pokemon_id: 7, user_id: user_id(0) }]);
+        assert_eq!(picked.current_player, Some(user_id(1)));
+
+        // nothing left to pick falls back to a skip
+        let (skipped, _) = apply(session.clone(), DraftAction::TurnTimeout { auto_select: None }, 31_000).unwrap();
+        assert_eq!(skipped.turn_ticker, 1);
+
+        let (paused, _) = apply(session, DraftAction::Pause, 5_000).unwrap();
+        assert_eq!(paused.turn_deadline, None);
+        let (resumed, _) = apply(paused, DraftAction::Resume, 50_000).unwrap();
+        assert_eq!(resumed.turn_deadline, Some(80_000));
+    }
+
+    #[test]
+    fn test_pause_resume_cancel() {
+        let session = started_session(2, DraftRules::default());
+
+        let (session, events) = apply(session, DraftAction::Pause, 0).unwrap();
+        assert_eq!(session.draft_state, DraftState::Paused);
+        assert_eq!(events, vec![DraftEvent::DraftPaused]);
+        assert_eq!(select_as(session.clone(), 0, 1, DraftPhase::Ban).unwrap_err(), RuleViolation::DraftPaused);
+        assert_eq!(
+            apply(session.clone(), DraftAction::Pause, 0).unwrap_err(),
+            RuleViolation::InvalidTransition { from: DraftState::Paused, to: DraftState::Paused }
+        );
+
+        let (session, _) = apply(session, DraftAction::Resume, 0).unwrap();
+        assert_eq!(session.draft_state, DraftState::InProgress);
+
+        let (session, _) = apply(session, DraftAction::Cancel, 0).unwrap();
+        assert_eq!(session.draft_state, DraftState::Cancelled);
+        assert_eq!(select_as(session.clone(), 0, 1, DraftPhase::Ban).unwrap_err(), RuleViolation::DraftOver);
+        assert!(apply(session, DraftAction::Cancel, 0).is_err());
+    }
+
+    #[test]
+    fn test_kick_and_reorder() {
+        let session = DraftSession {
+            players: Some(generate_players(3)),
+            current_player: Some(user_id(0)),
+            ..Default::default()
+        };
+
+        let (session, events) = apply(session, DraftAction::Kick { user_id: user_id(0) }, 0).unwrap();
+        assert_eq!(events, vec![DraftEvent::PlayerKicked { player: "Player 1".into() }]);
+        assert_eq!(session.current_player, Some(user_id(1)));
+        let orders: Vec<u32> = session.players.as_ref().unwrap().iter().map(|p| p.order_in_session).collect();
+        assert_eq!(orders, vec![0, 1]);
+
+        assert_eq!(
+            apply(session.clone(), DraftAction::Reorder { order: vec![user_id(2)] }, 0).unwrap_err(),
+            RuleViolation::InvalidOrder
+        );
+        assert_eq!(
+            apply(session.clone(), DraftAction::Reorder { order: vec![user_id(2), user_id(2)] }, 0).unwrap_err(),
+            RuleViolation::InvalidOrder
+        );
+
+        let (session, _) = apply(session, DraftAction::Reorder { order: vec![user_id(2), user_id(1)] }, 0).unwrap();
+        assert_eq!(session.current_player, Some(user_id(2)));
+        assert_eq!(session.get_player(&user_id(1)).unwrap().order_in_session, 1);
+
+        let started = DraftSession { draft_state: DraftState::InProgress, ..session };
+        assert_eq!(
+            apply(started, DraftAction::Kick { user_id: user_id(1) }, 0).unwrap_err(),
+            RuleViolation::DraftAlreadyStarted
+        );
+    }
+
+    #[test]
+    fn test_accepting_players() {
+        let session = DraftSession {
+            players: Some(generate_players(1)),
+            max_num_players: 2,
+            accepting_players: true,
+            ..Default::default()
+        };
+
+        let (session, _) = apply(session, DraftAction::SetAcceptingPlayers { accepting: false }, 0).unwrap();
+        let newcomer = DraftUser { name: "Newcomer".into(), ..Default::default() };
+        assert_eq!(
+            apply(session.clone(), DraftAction::Join { user: newcomer.clone() }, 0).unwrap_err(),
+            RuleViolation::NotAcceptingPlayers
+        );
+
+        let (session, _) = apply(session, DraftAction::SetAcceptingPlayers { accepting: true }, 0).unwrap();
+        let (session, _) = apply(session, DraftAction::Join { user: newcomer }, 0).unwrap();
+        assert_eq!(
+            apply(session, DraftAction::SetAcceptingPlayers { accepting: true }, 0).unwrap_err(),
+            RuleViolation::SessionFull
+        );
+    }
+
+    #[test]
+    fn test_state_transitions() {
+        let states = [Open, Ready, InProgress, Paused, Ended, Cancelled];
+        let allowed = [
+            (Open, Ready),
+            (Ready, Open),
+            (Ready, InProgress),
+            (InProgress, Paused),
+            (Paused, InProgress),
+            (InProgress, Ended),
+            (Ended, InProgress),
+            (Open, Cancelled),
+            (Ready, Cancelled),
+            (InProgress, Cancelled),
+            (Paused, Cancelled),
+        ];
+
+        for from in states {
+            for to in states {
+                assert_eq!(from.can_transition_to(to), allowed.contains(&(from, to)), "{from:?} -> {to:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_start_needs_enough_ready_players() {
+        let mut players = generate_players(2);
+        players[0].ready = true;
+        let session = DraftSession {
+            players: Some(players),
+            min_num_players: 3,
+            max_num_players: 3,
+            accepting_players: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            apply(session.clone(), DraftAction::Start, 0).unwrap_err(),
+            RuleViolation::NotEnoughPlayers { min: 3, joined: 2 }
+        );
+
+        let session = DraftSession { min_num_players: 2, ..session };
+        assert_eq!(apply(session.clone(), DraftAction::Start, 0).unwrap_err(), RuleViolation::PlayersNotReady);
+
+        let (session, _) = apply(session, DraftAction::ToggleReady { user_id: user_id(1) }, 0).unwrap();
+        assert_eq!(session.draft_state, Ready);
+        let (session, events) = apply(session, DraftAction::Start, 0).unwrap();
+        assert_eq!(session.draft_state, InProgress);
+        assert!(!session.accepting_players);
+        assert_eq!(events, vec![DraftEvent::DraftStarted]);
+
+        assert_eq!(apply(session.clone(), DraftAction::Start, 0).unwrap_err(), RuleViolation::DraftAlreadyStarted);
+        assert_eq!(
+            apply(session, DraftAction::Resume, 0).unwrap_err(),
+            RuleViolation::InvalidTransition { from: InProgress, to: InProgress }
+        );
+    }
+
+    #[test]
+    fn test_join_and_ready() {
+        let session = DraftSession {
+            players: Some(vec![]),
+            min_num_players: 2,
+            max_num_players: 2,
+            accepting_players: true,
+            ..Default::default()
+        };
+        let mut players = generate_players(2).into_iter();
+
+        let (session, _) = apply(session, DraftAction::Join { user: players.next().unwrap() }, 0).unwrap();
+        assert_eq!(session.current_player, Some(user_id(0)));
+        let taken = DraftUser { name: "Player 1".into(), ..Default::default() };
+        assert_eq!(apply(session.clone(), DraftAction::Join { user: taken }, 0).unwrap_err(), RuleViolation::NameTaken);
+
+        let (session, _) = apply(session, DraftAction::Join { user: players.next().unwrap() }, 0).unwrap();
+        assert!(!session.accepting_players);
+        let extra = DraftUser { name: "Player 3".into(), ..Default::default() };
+        assert_eq!(apply(session.clone(), DraftAction::Join { user: extra }, 0).unwrap_err(), RuleViolation::SessionFull);
+
+        let (session, _) = apply(session, DraftAction::ToggleReady { user_id: user_id(0) }, 0).unwrap();
+        assert_eq!(session.draft_state, DraftState::Open);
+        let (session, events) = apply(session, DraftAction::ToggleReady { user_id: user_id(1) }, 0).unwrap();
+        assert_eq!(session.draft_state, DraftState::Ready);
+        assert_eq!(events, vec![DraftEvent::ReadyToggled {
+            player: "Player 2".into(),
+            ready: true,
+            draft_state: DraftState::Ready,
+        }]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Every action a session goes through is kept as a `draft_event` record, in
+//! the order the engine applied them. Since the engine is deterministic given
+//! the action and its time, running the records back through it rebuilds the
+//! session as it was at any point.
+use serde::{Deserialize, Serialize};
+use surrealdb::RecordId;
+
+use super::engine::{self, DraftAction, RuleViolation};
+use super::{DraftSession, DraftState};
+use crate::events::DraftEvent;
+
+/// Who asked for an action.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Actor {
+    Player { name: String },
+    Host,
+    /// The turn timer running out.
+    Timer,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DraftEventRecord {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<RecordId>,
+    pub session: RecordId,
+    /// 1 for the session's first action, counting up.
+    pub seq: u32,
+    /// Unix milliseconds, the `now` the engine was given.
+    pub at: u64,
+    pub actor: Actor,
+    /// `turn_ticker` when the action came in.
+    pub turn: u32,
+    pub action: DraftAction,
+    pub events: Vec<DraftEvent>,
+}
+
+impl DraftEventRecord {
+    /// `turn` is the `turn_ticker` the action was applied on, `after` what came out.
+    pub fn new(
+        turn: u32,
+        after: &DraftSession,
+        action: &DraftAction,
+        actor: Actor,
+        at: u64,
+        events: &[DraftEvent],
+    ) -> Option<DraftEventRecord> {
+        Some(DraftEventRecord {
+            id: None,
+            session: after.id.clone()?,
+            seq: after.action_count,
+            at,
+            actor,
+            turn,
+            action: action.for_history(),
+            events: events.to_vec(),
+        })
+    }
+}
+
+/// The session as it was created, before any player joined.
+fn initial_state(session: &DraftSession) -> DraftSession {
+    DraftSession {
+        id: session.id.clone(),
+        name: session.name.clone(),
+        min_num_players: session.min_num_players,
+        max_num_players: session.max_num_players,
+        current_phase: session.draft_rules.starting_phase,
+        draft_rules: session.draft_rules.clone(),
+        draft_set: session.draft_set.clone(),
+        draft_pool: session.draft_pool.clone(),
+        pokedex: session.pokedex.clone(),
+        order_seed: session.order_seed,
+        // what the store hands back for a session nobody joined yet
+        players: Some(vec![]),
+        draft_state: DraftState::Open,
+        accepting_players: true,
+        ..Default::default()
+    }
+}
+
+/// Runs `records` back through the engine on top of a fresh copy of `session`,
+/// which needs its draft pool and pokedex in place the way a pick does. With a
+/// `turn`, stops at the first action that would take the draft past it, so the
+/// result is the session as it was right before that turn was played.
+pub fn replay(
+    session: &DraftSession,
+    records: &[DraftEventRecord],
+    turn: Option<u32>,
+) -> Result<DraftSession, RuleViolation> {
+    let mut state = initial_state(session);
+
+    for record in records {
+        let (next, _) = engine::apply(state.clone(), record.action.clone(), record.at)?;
+        if turn.is_some_and(|t| next.turn_ticker > t) {
+            break;
+        }
+        state = next;
+    }
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::models::draft::{DraftPhase, DraftRules, DraftUser};
+    use crate::models::key::KeyHash;
+    use crate::models::pokedex::Pokedex;
+    use std::sync::Arc;
+
+    fn user_id(i: u32) -> RecordId {
+        RecordId::from_table_key("draft_user", format!("p{i}"))
+    }
+
+    fn record_all(session: DraftSession, actions: Vec<DraftAction>) -> (DraftSession, Vec<DraftEventRecord>) {
+        let mut records = vec![];
+        let mut state = session;
+        for (i, action) in actions.into_iter().enumerate() {
+            let at = i as u64 * 1_000;
+            let (next, events) = engine::apply(state.clone(), action.clone(), at).unwrap();
+            records.push(DraftEventRecord::new(state.turn_ticker, &next, &action, Actor::Host, at, &events).unwrap());
+            state = next;
+        }
+        (state, records)
+    }
+
+    #[test]
+    fn test_replay_rebuilds_every_turn() {
+        let session = DraftSession {
+            id: Some(RecordId::from_table_key("draft_session", "s")),
+            players: Some(vec![]),
+            min_num_players: 2,
+            max_num_players: 2,
+            accepting_players: true,
+            draft_rules: DraftRules { turn_time_limit: Some(30), ..Default::default() },
+            pokedex: Arc::new(Pokedex::default()),
+            ..Default::default()
+        };
+
+        let mut actions = vec![];
+        for i in 0..2 {
+            let mut user = DraftUser::new(format!("Player {}", i + 1), KeyHash::new(&uuid::Uuid::new_v4()), i);
+            user.id = Some(user_id(i));
+            actions.push(DraftAction::Join { user });
+        }
+        actions.push(DraftAction::ToggleReady { user_id: user_id(0) });
+        actions.push(DraftAction::ToggleReady { user_id: user_id(1) });
+        actions.push(DraftAction::Start);
+        actions.push(DraftAction::Select { user_id: user_id(0), pokemon_id: 1, action: DraftPhase::Ban });
+        actions.push(DraftAction::Undo);
+        actions.push(DraftAction::Select { user_id: user_id(0), pokemon_id: 2, action: DraftPhase::Ban });
+        actions.push(DraftAction::Select { user_id: user_id(1), pokemon_id: 3, action: DraftPhase::Ban });
+
+        let (end, records) = record_all(session, actions);
+        assert_eq!(records.iter().map(|r| r.seq).collect::<Vec<_>>(), (1..=9).collect::<Vec<_>>());
+        assert_eq!(records[5].turn, 0);
+        assert_eq!(records[8].turn, 1);
+
+        // no secrets in the log
+        match &records[0].action {
+            DraftAction::Join { user } => assert_eq!(user.key_hash, KeyHash::default()),
+            other => panic!("expected a join, got {other:?}"),
+        }
+
+        let full = replay(&end, &records, None).unwrap();
+        assert!(Arc::ptr_eq(&full.pokedex, &end.pokedex));
+        assert_eq!(full.banned, end.banned);
+        assert_eq!(full.current_player, end.current_player);
+        assert_eq!(full.turn_ticker, end.turn_ticker);
+        assert_eq!(full.turn_deadline, end.turn_deadline);
+        assert_eq!(full.history, end.history);
+
+        let first_turn = replay(&end, &records, Some(0)).unwrap();
+        assert_eq!(first_turn.draft_state, DraftState::InProgress);
+        assert_eq!(first_turn.turn_ticker, 0);
+        assert!(first_turn.banned.is_empty());
+
+        let second_turn = replay(&end, &records, Some(1)).unwrap();
+        // the undone ban on turn 0 came and went, the redo is in
+        assert_eq!(second_turn.banned.len(), 1);
+        assert_eq!(second_turn.banned[0].pokemon_id, 2);
+        assert_eq!(second_turn.current_player, Some(user_id(1)));
+    }
+}
@@ -0,0 +1,563 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use surrealdb::RecordId;
+use uuid::Uuid;
+
+use super::key::KeyHash;
+use super::pokedex::Pokedex;
+use super::pokemon::DraftSetMember;
+use order::TurnStep;
+use roster::RosterConstraints;
+
+pub mod engine;
+pub mod history;
+pub mod order;
+pub mod roster;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum DraftState {
+    Open,               // Starting value. Allows players to join
+    Ready,              // All players have listed themselves as ready
+    InProgress,         // No more players may join, Pick/Bans in progress
+    Ended,              // Pick/Bans are done. All pokemon have been chosen
+    Paused,             // The host stopped the draft for now, nobody can pick or ban
+    Cancelled,          // The host called the draft off
+}
+
+impl DraftState {
+    /// Open <-> Ready -> InProgress <-> Paused, InProgress <-> Ended, and
+    /// anything that isn't over yet can be cancelled. Only undoing the last
+    /// pick or ban takes an ended draft back to InProgress.
+    pub fn can_transition_to(self, to: DraftState) -> bool {
+        use DraftState::*;
+
+        matches!(
+            (self, to),
+            (Open, Ready)
+                | (Ready, Open)
+                | (Ready, InProgress)
+                | (InProgress, Paused)
+                | (Paused, InProgress)
+                | (InProgress, Ended)
+                | (Ended, InProgress)
+                | (Open | Ready | InProgress | Paused, Cancelled)
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[warn(dead_code)]
+pub enum DraftPhase {
+    Pick,
+    Ban,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[warn(dead_code)]
+pub enum TurnType {
+    RoundRobin,
+    Snake,
+    /// Snake, except the third round goes the same way as the second.
+    ThirdRoundReversal,
+    /// Every round goes in order, starting one player further along each time.
+    LinearRotating,
+    /// A fresh shuffle of the players every round.
+    RandomPerRound,
+    /// Follows `sequence` step by step.
+    Custom,
+}
+
+/// What happens to a player whose turn runs out.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy, Default)]
+pub enum TimeoutAction {
+    #[default]
+    Skip,
+    /// Picks or bans a random pokemon still left in the draft set.
+    AutoSelect,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DraftRules {
+    pub id: Option<RecordId>,
+    name: String,
+    picks_per_round: u16,
+    bans_per_round: u16,
+    max_pokemon: u16,
+    starting_phase: DraftPhase,
+    turn_type: TurnType,
+    /// Seconds a player gets per turn, no limit when missing.
+    #[serde(default)]
+    turn_time_limit: Option<u32>,
+    #[serde(default)]
+    pub on_timeout: TimeoutAction,
+    /// The whole draft, turn by turn, for `TurnType::Custom`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sequence: Vec<TurnStep>,
+    /// Points each player can spend on picks, paid with the `points` the draft
+    /// set gives each pokemon. No budget when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    point_budget: Option<u32>,
+    /// Caps on how many pokemon a player can pick from each of the draft set's tiers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tier_limits: Vec<TierLimit>,
+    #[serde(default, skip_serializing_if = "RosterConstraints::is_empty")]
+    roster_constraints: RosterConstraints,
+}
+
+/// At most `max_picks` pokemon from `tier` on any one roster.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TierLimit {
+    pub tier: String,
+    pub max_picks: u32,
+}
+
+impl Default for DraftRules {
+    fn default() -> DraftRules {
+        DraftRules{
+            id: None,
+            name: "".to_string(),
+            picks_per_round: 1,
+            bans_per_round: 1,
+            max_pokemon: 1,
+            starting_phase: DraftPhase::Ban,
+            turn_type: TurnType::Snake,
+            turn_time_limit: None,
+            on_timeout: TimeoutAction::Skip,
+            sequence: vec![],
+            point_budget: None,
+            tier_limits: vec![],
+            roster_constraints: RosterConstraints::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DraftSession {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<RecordId>,
+    name: String,
+    pub min_num_players: u16,
+    pub max_num_players: u16,
+    #[serde(default)]
+    pub picked: Vec<Selection>,
+    #[serde(default)]
+    pub banned: Vec<Selection>,
+    /// Picks and bans together, all sessions had before `picked` and `banned`.
+    /// Read so those sessions keep their choices, never written.
+    #[serde(default, rename = "selected_pokemon", skip_serializing)]
+    legacy_selected: Vec<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub players: Option<Vec<DraftUser>>,
+    pub draft_rules: DraftRules,
+    pub draft_set: Option<String>,
+    pub current_player: Option<RecordId>,
+    pub turn_ticker: u32,
+    // TODO: Use enum here DraftState::{ACCEPTING_PLAYER, MIN_JOINED, MAX_JOINED, ONGOING, DONE}
+    pub accepting_players: bool,
+    pub draft_state: DraftState,
+    pub current_phase: DraftPhase,
+    /// Sessions made before hosts existed don't have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host_key_hash: Option<KeyHash>,
+    /// Unix milliseconds when the current turn runs out.
+    #[serde(default)]
+    pub turn_deadline: Option<u64>,
+    /// The members of `draft_set`, resolved once so every pick doesn't have to
+    /// follow the set's `contains` edges again.
+    #[serde(default)]
+    pub draft_pool: Option<Vec<DraftSetMember>>,
+    /// Every pick and ban, and every undo of one, oldest first.
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+    /// How many actions the engine has applied, the `seq` of the latest `draft_event`.
+    #[serde(default)]
+    pub action_count: u32,
+    /// Seeds `TurnType::RandomPerRound`. Kept from clients, it would tell them
+    /// the turn order ahead of time.
+    #[serde(default)]
+    pub order_seed: u64,
+    /// What the roster constraints judge picks by, the shared pokedex handed
+    /// in before a pick and never stored.
+    #[serde(skip)]
+    pub pokedex: Arc<Pokedex>,
+}
+
+/// A pokemon someone picked or banned.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Selection {
+    pub pokemon_id: u32,
+    pub user_id: RecordId,
+}
+
+/// Enough about a pick or ban to take it back. `turn` is the `turn_ticker` it
+/// was made on.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(tag = "type")]
+pub enum HistoryEntry {
+    Selection { user_id: RecordId, pokemon_id: u32, action: DraftPhase, turn: u32, undone: bool },
+    Undo { user_id: RecordId, pokemon_id: u32, action: DraftPhase, turn: u32 },
+}
+
+// TODO: Impl Serialize
+
+// TODO: Impl Deserialize
+
+impl Default for DraftSession {
+    fn default() -> DraftSession {
+        DraftSession {
+            id: None,
+            name: "".to_string(),
+            min_num_players: 4,
+            max_num_players: 4,
+            picked: vec![],
+            banned: vec![],
+            legacy_selected: vec![],
+            players: None,
+            draft_rules: DraftRules {
+                ..Default::default()
+            },
+            draft_set: None,
+            current_player: None,
+            turn_ticker: 0,
+            accepting_players: false,
+            draft_state: DraftState::Open,
+            current_phase: DraftPhase::Ban,
+            host_key_hash: None,
+            turn_deadline: None,
+            draft_pool: None,
+            history: vec![],
+            action_count: 0,
+            order_seed: 0,
+            pokedex: Arc::default(),
+        }
+    }
+}
+
+impl DraftSession {
+    pub fn from(form: DraftSessionCreateForm, rules: DraftRules) -> DraftSession {
+        DraftSession {
+            id: None,
+            name: form.name,
+            min_num_players: form.min_num_players,
+            max_num_players: form.max_num_players,
+            picked: Vec::new(),
+            banned: Vec::new(),
+            legacy_selected: Vec::new(),
+            players: None,
+            current_phase: rules.starting_phase,
+            draft_rules: rules,
+            draft_set: Some(form.draft_set),
+            current_player: None,
+            turn_ticker: 0,
+            draft_state: DraftState::Open,
+            accepting_players: true,
+            host_key_hash: None,
+            turn_deadline: None,
+            draft_pool: None,
+            history: vec![],
+            action_count: 0,
+            order_seed: 0,
+            pokedex: Arc::default(),
+        }
+    }
+
+    pub fn is_name_taken(&self, name: &str) -> bool {
+        let players: &Vec<DraftUser> = match &self.players {
+            Some(s) => s,
+            None => return false,
+        };
+        players.iter().any(|x| x.name == name)
+    }
+
+    pub fn get_player(&self, id: &RecordId) -> Option<&DraftUser> {
+        self.players.as_ref()?.iter().find(|p| p.id.as_ref() == Some(id))
+    }
+
+    pub fn get_player_mut(&mut self, id: &RecordId) -> Option<&mut DraftUser> {
+        self.players.as_mut()?.iter_mut().find(|p| p.id.as_ref() == Some(id))
+    }
+
+    pub fn is_current_player(&self, id: &RecordId) -> bool {
+        if let Some(ref t) = self.current_player {
+            return t == id
+        }
+        false
+    }
+
+    pub fn get_current_player_name(&self) -> Option<String> {
+        if let Some(players) = &self.players {
+            for player in players {
+                if player.id == self.current_player {
+                    return Some(player.name.clone());
+                }
+            }
+        }
+        None
+    }
+
+    pub fn num_of_players(&self) -> u32 {
+        match &self.players {
+            None => 0,
+            Some(p) => p.len() as u32,
+        }
+    }
+
+    /// Whether picks get checked against the pokemon themselves, and so need `pokedex`.
+    pub fn has_roster_constraints(&self) -> bool {
+        !self.draft_rules.roster_constraints.is_empty()
+    }
+
+    pub fn check_host_key(&self, key: &Uuid) -> bool {
+        match &self.host_key_hash {
+            Some(hash) => hash.verify(key),
+            None => false,
+        }
+    }
+
+    /// Sessions whose pool was never resolved take anything.
+    pub fn is_in_draft_pool(&self, pk: &u32) -> bool {
+        match &self.draft_pool {
+            Some(pool) => pool.iter().any(|m| m.dex_id == *pk),
+            None => true,
+        }
+    }
+
+    fn pool_member(&self, pk: &u32) -> Option<&DraftSetMember> {
+        self.draft_pool.iter().flatten().find(|m| m.dex_id == *pk)
+    }
+
+    /// What `pk` costs to pick, 0 when the draft set doesn't say.
+    pub fn points_of(&self, pk: &u32) -> u32 {
+        self.pool_member(pk).and_then(|m| m.points).unwrap_or(0)
+    }
+
+    pub fn tier_of(&self, pk: &u32) -> Option<&str> {
+        self.pool_member(pk)?.tier.as_deref()
+    }
+
+    /// How many of `user_id`'s picks came from `tier`.
+    pub fn picks_in_tier(&self, user_id: &RecordId, tier: &str) -> u32 {
+        self.picked
+            .iter()
+            .filter(|s| s.user_id == *user_id && self.tier_of(&s.pokemon_id) == Some(tier))
+            .count() as u32
+    }
+
+    /// Points `user_id` has left to spend, `None` when the rules have no budget.
+    pub fn points_remaining(&self, user_id: &RecordId) -> Option<u32> {
+        let budget = self.draft_rules.point_budget?;
+        let spent: u32 = self.picked
+            .iter()
+            .filter(|s| s.user_id == *user_id)
+            .map(|s| self.points_of(&s.pokemon_id))
+            .sum();
+        Some(budget.saturating_sub(spent))
+    }
+
+    /// Whether `pk` would fit on `user_id`'s roster, see [`engine::check_roster`].
+    pub fn can_pick(&self, user_id: &RecordId, pk: &u32) -> bool {
+        engine::check_roster(self, user_id, *pk).is_ok()
+    }
+
+    pub fn is_pokemon_chosen(&self, pk: &u32) -> bool {
+        self.picked.iter().chain(&self.banned).any(|s| s.pokemon_id == *pk) || self.legacy_selected.contains(pk)
+    }
+
+    /// First pokemon in `user_id`'s queue that's still up for grabs and that
+    /// fits on their roster.
+    pub fn next_queued(&self, user_id: &RecordId) -> Option<u32> {
+        let player = self.get_player(user_id)?;
+        player.pick_queue.iter()
+            .copied()
+            .find(|pk| self.is_in_draft_pool(pk) && !self.is_pokemon_chosen(pk) && self.can_pick(user_id, pk))
+    }
+}
+
+// Maybe just change this into a regular form?
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DraftSessionCreateForm {
+    pub name: String,
+    pub draft_set: String,
+    pub draft_rules: String,
+    min_num_players: u16,
+    max_num_players: u16,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DraftUser {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<RecordId>,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session: Option<RecordId>,
+    pub selected_pokemon: Vec<u32>,
+    key_hash: KeyHash,
+    pub order_in_session: u32,
+    pub ready: bool,
+    /// Pokemon the player wants, best first. Only the player gets to see it,
+    /// and only `set_pick_queue` writes it to the store, so an action saving
+    /// the player can't put back a queue the player just replaced.
+    #[serde(default, skip_serializing)]
+    pick_queue: Vec<u32>,
+}
+
+impl Default for DraftUser {
+    fn default() -> DraftUser {
+        DraftUser {
+            id: None,
+            name: "".to_string(),
+            session: None,
+            selected_pokemon: vec![],
+            key_hash: KeyHash::default(),
+            order_in_session: 0,
+            ready: false,
+            pick_queue: vec![],
+        }
+    }
+}
+
+impl DraftUser {
+    pub fn new(name: String, key: KeyHash, order: u32) -> DraftUser {
+        DraftUser {
+            id: None,
+            name,
+            session: None,
+            selected_pokemon: Vec::new(),
+            key_hash: key,
+            order_in_session: order,
+            ready: false,
+            pick_queue: vec![],
+        }
+    }
+
+    pub fn check_key(&self, key: &Uuid) -> bool {
+        self.key_hash.verify(key)
+    }
+
+    /// Re-hashes a key that was stored with the old scheme, returning the new
+    /// hash to store. Only call this once `check_key` has accepted `key`.
+    pub fn upgrade_key_hash(&mut self, key: &Uuid) -> Option<&KeyHash> {
+        if !self.key_hash.is_legacy() {
+            return None;
+        }
+        self.key_hash = KeyHash::new(key);
+        Some(&self.key_hash)
+    }
+
+    pub fn set_key_hash(&mut self, key_hash: KeyHash) {
+        self.key_hash = key_hash;
+    }
+
+    /// The player as the event log keeps them, without their key or queue.
+    pub fn without_secrets(&self) -> DraftUser {
+        DraftUser {
+            key_hash: KeyHash::default(),
+            pick_queue: vec![],
+            ..self.clone()
+        }
+    }
+
+    pub fn pick_queue(&self) -> &[u32] {
+        &self.pick_queue
+    }
+
+    /// Replaces the queue, keeping the first of any repeated entries.
+    pub fn set_pick_queue(&mut self, queue: Vec<u32>) {
+        self.pick_queue.clear();
+        for pk in queue {
+            if !self.pick_queue.contains(&pk) {
+                self.pick_queue.push(pk);
+            }
+        }
+    }
+
+    /// Moves `pk` to `position`, or to the back when `position` is past the end.
+    /// Returns false if `pk` isn't queued.
+    pub fn move_in_pick_queue(&mut self, pk: u32, position: usize) -> bool {
+        let from = match self.pick_queue.iter().position(|p| *p == pk) {
+            Some(i) => i,
+            None => return false,
+        };
+        self.pick_queue.remove(from);
+        let to = position.min(self.pick_queue.len());
+        self.pick_queue.insert(to, pk);
+        true
+    }
+
+    pub fn drop_from_pick_queue(&mut self, pk: u32) {
+        self.pick_queue.retain(|p| *p != pk);
+    }
+
+    pub fn apply(&mut self, update: &PlayerUpdate) {
+        if let Some(selected) = &update.selected_pokemon {
+            self.selected_pokemon = selected.clone();
+        }
+        if let Some(order) = update.order_in_session {
+            self.order_in_session = order;
+        }
+        if let Some(ready) = update.ready {
+            self.ready = ready;
+        }
+    }
+}
+
+/// The fields of a player an engine action changed. Saving only these leaves
+/// alone whatever another request wrote to the player in the meantime.
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+pub struct PlayerUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selected_pokemon: Option<Vec<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_in_session: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ready: Option<bool>,
+}
+
+impl PlayerUpdate {
+    pub fn between(before: &DraftUser, after: &DraftUser) -> PlayerUpdate {
+        PlayerUpdate {
+            selected_pokemon: Some(after.selected_pokemon.clone()).filter(|s| *s != before.selected_pokemon),
+            order_in_session: Some(after.order_in_session).filter(|o| *o != before.order_in_session),
+            ready: Some(after.ready).filter(|r| *r != before.ready),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == PlayerUpdate::default()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DraftUserReturnData {
+    name: String,
+    session_id: String,
+    user_id: RecordId,
+    current_turn: bool,
+    /// Goes in `Authorization: Bearer <token>` for this player's requests.
+    token: String,
+}
+
+impl DraftUserReturnData {
+    pub fn new(
+        name: String,
+        session_id: String,
+        user_id: RecordId,
+        current_turn: bool,
+        token: String,
+    ) -> DraftUserReturnData {
+        DraftUserReturnData {
+            name,
+            session_id,
+            user_id,
+            current_turn,
+            token,
+        }
+    }
+}
+
+// is there a way to not do this?
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DraftUserForm {
+    pub name: String,
+}
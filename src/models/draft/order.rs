@@ -0,0 +1,250 @@
+//! Turn orders. A draft is a fixed sequence of steps, each one a player slot
+//! (their `order_in_session`) and whether that player picks or bans. The
+//! built in [`TurnType`]s lay the sequence out round by round, `Custom` takes
+//! it straight from the rules.
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::{DraftPhase, DraftRules, TurnType};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub struct TurnStep {
+    pub slot: u32,
+    pub action: DraftPhase,
+}
+
+/// Walks the turns of a draft in order. Ends once every player has had
+/// `max_pokemon` picks, or with the last step of a custom sequence.
+pub struct TurnSequence {
+    turn_type: TurnType,
+    picks_per_round: u32,
+    bans_per_round: u32,
+    max_pokemon: u32,
+    starting_phase: DraftPhase,
+    num_players: u32,
+    seed: u64,
+    round: u32,
+    pick_rounds: u32,
+    pending: VecDeque<TurnStep>,
+}
+
+impl TurnSequence {
+    /// `seed` only matters for `RandomPerRound`, the same seed always gives the
+    /// same order so a session can be replayed.
+    pub fn new(rules: &DraftRules, num_players: u32, seed: u64) -> TurnSequence {
+        let pending = match rules.turn_type {
+            TurnType::Custom => rules.sequence.iter().copied().collect(),
+            _ => VecDeque::new(),
+        };
+
+        TurnSequence {
+            turn_type: rules.turn_type,
+            picks_per_round: rules.picks_per_round as u32,
+            bans_per_round: rules.bans_per_round as u32,
+            max_pokemon: rules.max_pokemon as u32,
+            starting_phase: rules.starting_phase,
+            num_players,
+            seed,
+            round: 0,
+            pick_rounds: 0,
+            pending,
+        }
+    }
+
+    /// Ban rounds and pick rounds take turns, `starting_phase` goes first.
+    fn phase_of(&self, round: u32) -> DraftPhase {
+        let n = round % (self.bans_per_round + self.picks_per_round);
+        match self.starting_phase {
+            DraftPhase::Ban if n < self.bans_per_round => DraftPhase::Ban,
+            DraftPhase::Ban => DraftPhase::Pick,
+            DraftPhase::Pick if n < self.picks_per_round => DraftPhase::Pick,
+            DraftPhase::Pick => DraftPhase::Ban,
+        }
+    }
+
+    fn order_of(&self, round: u32) -> Vec<u32> {
+        let n = self.num_players;
+        let forward: Vec<u32> = (0..n).collect();
+        let reversed: Vec<u32> = (0..n).rev().collect();
+
+        match self.turn_type {
+            TurnType::RoundRobin | TurnType::Custom => forward,
+            TurnType::Snake if round % 2 == 1 => reversed,
+            TurnType::Snake => forward,
+            // 1 2 3, 3 2 1, 3 2 1, 1 2 3, then snaking on from there
+            TurnType::ThirdRoundReversal if round == 1 || (round >= 2 && round % 2 == 0) => reversed,
+            TurnType::ThirdRoundReversal => forward,
+            TurnType::LinearRotating => (0..n).map(|i| (round + i) % n).collect(),
+            TurnType::RandomPerRound => shuffled(forward, self.seed ^ u64::from(round).wrapping_mul(GOLDEN_GAMMA)),
+        }
+    }
+
+    fn fill_round(&mut self) -> bool {
+        let built_in = self.turn_type != TurnType::Custom;
+        if !built_in || self.num_players == 0 || self.picks_per_round == 0 || self.pick_rounds >= self.max_pokemon {
+            return false;
+        }
+
+        let action = self.phase_of(self.round);
+        if action == DraftPhase::Pick {
+            self.pick_rounds += 1;
+        }
+        for slot in self.order_of(self.round) {
+            self.pending.push_back(TurnStep { slot, action });
+        }
+        self.round += 1;
+        true
+    }
+}
+
+impl Iterator for TurnSequence {
+    type Item = TurnStep;
+
+    fn next(&mut self) -> Option<TurnStep> {
+        loop {
+            if let Some(step) = self.pending.pop_front() {
+                return Some(step);
+            }
+            if !self.fill_round() {
+                return None;
+            }
+        }
+    }
+}
+
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+// splitmix64, kept here rather than pulling a seeded rng from `rand` so a
+// stored seed keeps giving the same order whatever `rand` version is around
+fn splitmix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(GOLDEN_GAMMA);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn shuffled(mut slots: Vec<u32>, seed: u64) -> Vec<u32> {
+    let mut state = seed;
+    for i in (1..slots.len()).rev() {
+        let j = (splitmix(&mut state) % (i as u64 + 1)) as usize;
+        slots.swap(i, j);
+    }
+    slots
+}
+
+impl DraftRules {
+    /// Rules whose turn order could never get a draft going.
+    pub fn check_turn_order(&self) -> Result<(), &'static str> {
+        match self.turn_type {
+            TurnType::Custom if self.sequence.is_empty() => Err("A custom turn order needs a sequence of steps"),
+            TurnType::Custom => Ok(()),
+            _ if self.picks_per_round == 0 || self.max_pokemon == 0 => {
+                Err("picks_per_round and max_pokemon have to be at least 1")
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn slots(rules: &DraftRules, num_players: u32) -> Vec<u32> {
+        TurnSequence::new(rules, num_players, 7).map(|s| s.slot).collect()
+    }
+
+    fn rules(turn_type: TurnType, max_pokemon: u16) -> DraftRules {
+        DraftRules {
+            picks_per_round: 1,
+            bans_per_round: 0,
+            max_pokemon,
+            starting_phase: DraftPhase::Pick,
+            turn_type,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sequence_lengths() {
+        let length = |players, picks, bans, max, starting_phase| {
+            let rules = DraftRules {
+                picks_per_round: picks,
+                bans_per_round: bans,
+                max_pokemon: max,
+                starting_phase,
+                ..Default::default()
+            };
+            TurnSequence::new(&rules, players, 0).count()
+        };
+
+        // a ban round, then a pick round
+        assert_eq!(length(4, 1, 1, 1, DraftPhase::Ban), 8);
+        // B B P P B B P P B B P
+        assert_eq!(length(3, 2, 2, 5, DraftPhase::Ban), 33);
+        // B P B P B P, or P B P B P when picks go first
+        assert_eq!(length(2, 1, 1, 3, DraftPhase::Ban), 12);
+        assert_eq!(length(2, 1, 1, 3, DraftPhase::Pick), 10);
+        assert_eq!(length(2, 0, 1, 3, DraftPhase::Ban), 0);
+    }
+
+    #[test]
+    fn test_phases_follow_the_rounds() {
+        let rules = DraftRules {
+            picks_per_round: 2,
+            bans_per_round: 1,
+            max_pokemon: 2,
+            starting_phase: DraftPhase::Ban,
+            turn_type: TurnType::RoundRobin,
+            ..Default::default()
+        };
+        let phases: Vec<DraftPhase> = TurnSequence::new(&rules, 2, 0).map(|s| s.action).collect();
+        use DraftPhase::*;
+        assert_eq!(phases, vec![Ban, Ban, Pick, Pick, Pick, Pick]);
+    }
+
+    #[test]
+    fn test_built_in_orders() {
+        assert_eq!(slots(&rules(TurnType::RoundRobin, 3), 3), vec![0, 1, 2, 0, 1, 2, 0, 1, 2]);
+        assert_eq!(slots(&rules(TurnType::Snake, 3), 3), vec![0, 1, 2, 2, 1, 0, 0, 1, 2]);
+        assert_eq!(
+            slots(&rules(TurnType::ThirdRoundReversal, 5), 3),
+            vec![0, 1, 2, 2, 1, 0, 2, 1, 0, 0, 1, 2, 2, 1, 0]
+        );
+        assert_eq!(slots(&rules(TurnType::LinearRotating, 3), 3), vec![0, 1, 2, 1, 2, 0, 2, 0, 1]);
+    }
+
+    #[test]
+    fn test_random_per_round() {
+        let rules = rules(TurnType::RandomPerRound, 20);
+        let order = slots(&rules, 4);
+        assert_eq!(order.len(), 80);
+        for round in order.chunks(4) {
+            let mut sorted = round.to_vec();
+            sorted.sort();
+            assert_eq!(sorted, vec![0, 1, 2, 3]);
+        }
+        // same seed, same order
+        assert_eq!(order, slots(&rules, 4));
+        let other: Vec<u32> = TurnSequence::new(&rules, 4, 8).map(|s| s.slot).collect();
+        assert_ne!(order, other);
+    }
+
+    #[test]
+    fn test_custom_sequence() {
+        let sequence = vec![
+            TurnStep { slot: 1, action: DraftPhase::Ban },
+            TurnStep { slot: 0, action: DraftPhase::Pick },
+            TurnStep { slot: 1, action: DraftPhase::Pick },
+            TurnStep { slot: 1, action: DraftPhase::Pick },
+        ];
+        let rules = DraftRules { turn_type: TurnType::Custom, sequence: sequence.clone(), ..Default::default() };
+        assert_eq!(TurnSequence::new(&rules, 2, 0).collect::<Vec<_>>(), sequence);
+        assert!(rules.check_turn_order().is_ok());
+
+        let empty = DraftRules { turn_type: TurnType::Custom, ..Default::default() };
+        assert!(empty.check_turn_order().is_err());
+    }
+}
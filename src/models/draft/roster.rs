@@ -0,0 +1,202 @@
+//! Limits on what a roster can hold, judged by the pokemon themselves: how
+//! many legendaries, mythics or pokemon of one type, which types it has to
+//! cover and whether two pokemon can share an evolution line. The checks need
+//! the session's `pokedex`, anything missing from it passes.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::pokedex::Pokedex;
+use crate::models::pokemon::{Pokemon, PokemonType};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub struct RosterConstraints {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_legendaries: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_mythics: Option<u32>,
+    /// How many pokemon on a roster can share any one type.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_per_type: Option<u32>,
+    /// Types a finished roster needs at least one pokemon of.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_types: Vec<PokemonType>,
+    /// No two pokemon that evolve from the same base form.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub one_per_evolution_line: bool,
+}
+
+impl RosterConstraints {
+    pub fn is_empty(&self) -> bool {
+        *self == RosterConstraints::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RosterViolation {
+    TooManyLegendaries { max: u32 },
+    TooManyMythics { max: u32 },
+    TooManyOfType { pokemon_type: PokemonType, max: u32 },
+    /// The picks left after this one couldn't cover `missing` any more.
+    TypesNotCovered { missing: Vec<PokemonType> },
+    /// `pokemon_id` on the roster is from the same evolution line.
+    EvolutionLineTaken { pokemon_id: u32 },
+}
+
+impl RosterViolation {
+    /// The field of [`RosterConstraints`] that failed.
+    pub fn constraint(&self) -> &'static str {
+        match self {
+            RosterViolation::TooManyLegendaries { .. } => "max_legendaries",
+            RosterViolation::TooManyMythics { .. } => "max_mythics",
+            RosterViolation::TooManyOfType { .. } => "max_per_type",
+            RosterViolation::TypesNotCovered { .. } => "required_types",
+            RosterViolation::EvolutionLineTaken { .. } => "one_per_evolution_line",
+        }
+    }
+}
+
+impl fmt::Display for RosterViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RosterViolation::TooManyLegendaries { max } => write!(f, "A roster can have at most {max} legendaries"),
+            RosterViolation::TooManyMythics { max } => write!(f, "A roster can have at most {max} mythics"),
+            RosterViolation::TooManyOfType { pokemon_type, max } => {
+                write!(f, "A roster can have at most {max} pokemon of type {pokemon_type:?}")
+            },
+            RosterViolation::TypesNotCovered { missing } => {
+                write!(f, "The picks you have left couldn't cover the required types {missing:?}")
+            },
+            RosterViolation::EvolutionLineTaken { pokemon_id } => {
+                write!(f, "Pokemon {pokemon_id} on your roster is from the same evolution line")
+            },
+        }
+    }
+}
+
+/// Whether `pick` can go on a roster holding `roster`. `picks_left` is how
+/// many picks the player still gets after this one, each of which could cover
+/// two of the required types.
+pub fn check(
+    constraints: &RosterConstraints,
+    pokedex: &Pokedex,
+    roster: &[u32],
+    pick: u32,
+    picks_left: u32,
+) -> Result<(), RosterViolation> {
+    let pokemon = match pokedex.get(pick) {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+    let team: Vec<&Pokemon> = roster.iter().filter_map(|id| pokedex.get(*id)).collect();
+
+    if let Some(max) = constraints.max_legendaries {
+        if pokemon.is_legendary && team.iter().filter(|p| p.is_legendary).count() as u32 >= max {
+            return Err(RosterViolation::TooManyLegendaries { max });
+        }
+    }
+    if let Some(max) = constraints.max_mythics {
+        if pokemon.is_mythic && team.iter().filter(|p| p.is_mythic).count() as u32 >= max {
+            return Err(RosterViolation::TooManyMythics { max });
+        }
+    }
+    if let Some(max) = constraints.max_per_type {
+        for pokemon_type in pokemon.types() {
+            if team.iter().filter(|p| p.types().contains(&pokemon_type)).count() as u32 >= max {
+                return Err(RosterViolation::TooManyOfType { pokemon_type, max });
+            }
+        }
+    }
+    if constraints.one_per_evolution_line {
+        if let Some(p) = team.iter().find(|p| pokedex.evolutions().same_family(p.dex_id, pick)) {
+            return Err(RosterViolation::EvolutionLineTaken { pokemon_id: p.dex_id });
+        }
+    }
+
+    let missing: Vec<PokemonType> = constraints.required_types
+        .iter()
+        .copied()
+        .filter(|t| !pokemon.types().contains(t) && !team.iter().any(|p| p.types().contains(t)))
+        .collect();
+    if missing.len() as u32 > picks_left * 2 {
+        return Err(RosterViolation::TypesNotCovered { missing });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use PokemonType::*;
+
+    fn pokedex() -> Vec<Pokemon> {
+        vec![
+            Pokemon::test(1).with_types(GRASS, POISON),
+            Pokemon::test(2).with_types(GRASS, POISON).with_evolves_from(1),
+            Pokemon::test(3).with_types(GRASS, POISON).with_evolves_from(2),
+            Pokemon::test(4).with_type(FIRE),
+            Pokemon::test(6).with_types(FIRE, FLYING).with_evolves_from(5),
+            Pokemon::test(7).with_type(WATER),
+            Pokemon::test(144).with_types(ICE, FLYING).legendary(),
+            Pokemon::test(145).with_types(ELECTRIC, FLYING).legendary(),
+            Pokemon::test(151).with_type(PSYCHIC).mythic(),
+        ]
+    }
+
+    #[test]
+    fn test_legendaries_and_mythics() {
+        let constraints = RosterConstraints { max_legendaries: Some(1), max_mythics: Some(0), ..Default::default() };
+        let dex = Pokedex::new(pokedex());
+
+        assert!(check(&constraints, &dex, &[1], 144, 5).is_ok());
+        assert_eq!(check(&constraints, &dex, &[144], 145, 5), Err(RosterViolation::TooManyLegendaries { max: 1 }));
+        assert_eq!(check(&constraints, &dex, &[], 151, 5), Err(RosterViolation::TooManyMythics { max: 0 }));
+        // pokemon the pokedex doesn't know about can't be judged
+        assert!(check(&constraints, &dex, &[144], 999, 5).is_ok());
+    }
+
+    #[test]
+    fn test_max_per_type() {
+        let constraints = RosterConstraints { max_per_type: Some(1), ..Default::default() };
+        let dex = Pokedex::new(pokedex());
+
+        assert!(check(&constraints, &dex, &[4], 7, 5).is_ok());
+        assert_eq!(
+            check(&constraints, &dex, &[144], 6, 5),
+            Err(RosterViolation::TooManyOfType { pokemon_type: FLYING, max: 1 })
+        );
+        assert_eq!(pokedex()[3].types(), vec![FIRE]);
+    }
+
+    #[test]
+    fn test_one_per_evolution_line() {
+        let constraints = RosterConstraints { one_per_evolution_line: true, ..Default::default() };
+        let dex = Pokedex::new(pokedex());
+
+        assert_eq!(check(&constraints, &dex, &[4, 1], 3, 5), Err(RosterViolation::EvolutionLineTaken { pokemon_id: 1 }));
+        assert_eq!(check(&constraints, &dex, &[2], 1, 5), Err(RosterViolation::EvolutionLineTaken { pokemon_id: 2 }));
+        assert!(check(&constraints, &dex, &[1], 4, 5).is_ok());
+        // Charmeleon isn't in this pokedex, so Charizard's line stops at 5
+        assert!(check(&constraints, &dex, &[4], 6, 5).is_ok());
+    }
+
+    #[test]
+    fn test_required_types() {
+        let constraints = RosterConstraints { required_types: vec![FIRE, WATER, ICE], ..Default::default() };
+        let dex = Pokedex::new(pokedex());
+
+        assert!(check(&constraints, &dex, &[], 1, 2).is_ok());
+        // one pick left after this one can't cover three types
+        assert_eq!(
+            check(&constraints, &dex, &[], 1, 1),
+            Err(RosterViolation::TypesNotCovered { missing: vec![FIRE, WATER, ICE] })
+        );
+        assert!(check(&constraints, &dex, &[4], 144, 1).is_ok());
+        assert_eq!(
+            check(&constraints, &dex, &[4, 144], 2, 0),
+            Err(RosterViolation::TypesNotCovered { missing: vec![WATER] })
+        );
+        assert!(check(&constraints, &dex, &[4, 144], 7, 0).is_ok());
+    }
+}
@@ -0,0 +1,195 @@
+//! Evolution lines. `Pokemon::evolves_from` only points one step back, the
+//! graph links both ways so a pokemon's whole family can be walked from any
+//! member of it, branches included.
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+
+use super::pokemon::Pokemon;
+
+#[derive(Debug, Default)]
+pub struct EvolutionGraph {
+    names: BTreeMap<u32, String>,
+    evolves_from: BTreeMap<u32, u32>,
+    evolves_into: BTreeMap<u32, Vec<u32>>,
+}
+
+/// A pokemon and everything it evolves into, for the family tree.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct EvolutionNode {
+    pub dex_id: u32,
+    pub name: String,
+    pub evolves_into: Vec<EvolutionNode>,
+}
+
+/// Where a pokemon sits in its family.
+#[derive(Debug, Serialize)]
+pub struct EvolutionFamily {
+    pub dex_id: u32,
+    pub base_form: u32,
+    /// What it evolves from, base form first.
+    pub pre_evolutions: Vec<u32>,
+    /// Everything it can evolve into, directly or not.
+    pub evolutions: Vec<u32>,
+    /// Every member of the family, in dex order.
+    pub family: Vec<u32>,
+    /// The family tree from the base form down.
+    pub tree: EvolutionNode,
+}
+
+impl EvolutionGraph {
+    /// Links pokemon that evolve from something in `pokedex`. A pre-evolution
+    /// the pokedex doesn't have ends the line there.
+    pub fn new(pokedex: &[Pokemon]) -> EvolutionGraph {
+        let mut graph = EvolutionGraph::default();
+        for p in pokedex {
+            graph.names.insert(p.dex_id, p.name.clone());
+        }
+        for p in pokedex {
+            let from = u32::from(p.evolves_from);
+            if from != 0 && from != p.dex_id && graph.names.contains_key(&from) {
+                graph.evolves_from.insert(p.dex_id, from);
+                graph.evolves_into.entry(from).or_default().push(p.dex_id);
+            }
+        }
+        for into in graph.evolves_into.values_mut() {
+            into.sort();
+        }
+        graph
+    }
+
+    pub fn contains(&self, dex_id: u32) -> bool {
+        self.names.contains_key(&dex_id)
+    }
+
+    /// Everything `dex_id` evolves from, closest first.
+    fn ancestors(&self, dex_id: u32) -> Vec<u32> {
+        let mut line = vec![];
+        let mut current = dex_id;
+        while let Some(from) = self.evolves_from.get(&current) {
+            // a loop in the data, not a real evolution line
+            if *from == dex_id || line.contains(from) {
+                break;
+            }
+            line.push(*from);
+            current = *from;
+        }
+        line
+    }
+
+    /// The form `dex_id`'s line starts from, itself if it doesn't evolve from anything.
+    pub fn base_form(&self, dex_id: u32) -> u32 {
+        self.ancestors(dex_id).last().copied().unwrap_or(dex_id)
+    }
+
+    pub fn same_family(&self, a: u32, b: u32) -> bool {
+        self.base_form(a) == self.base_form(b)
+    }
+
+    fn descendants(&self, dex_id: u32, seen: &mut HashSet<u32>) -> Vec<u32> {
+        let mut found = vec![];
+        for into in self.evolves_into.get(&dex_id).into_iter().flatten() {
+            if seen.insert(*into) {
+                found.push(*into);
+                found.extend(self.descendants(*into, seen));
+            }
+        }
+        found
+    }
+
+    fn node(&self, dex_id: u32, seen: &mut HashSet<u32>) -> EvolutionNode {
+        seen.insert(dex_id);
+        let evolves_into = self.evolves_into
+            .get(&dex_id)
+            .into_iter()
+            .flatten()
+            .filter(|into| !seen.contains(into))
+            .copied()
+            .collect::<Vec<u32>>()
+            .into_iter()
+            .map(|into| self.node(into, seen))
+            .collect();
+
+        EvolutionNode {
+            dex_id,
+            name: self.names.get(&dex_id).cloned().unwrap_or_default(),
+            evolves_into,
+        }
+    }
+
+    /// `None` when the pokedex doesn't have `dex_id`.
+    pub fn family(&self, dex_id: u32) -> Option<EvolutionFamily> {
+        if !self.contains(dex_id) {
+            return None;
+        }
+
+        let mut pre_evolutions = self.ancestors(dex_id);
+        pre_evolutions.reverse();
+        let base_form = self.base_form(dex_id);
+
+        let mut evolutions = self.descendants(dex_id, &mut HashSet::from([dex_id]));
+        evolutions.sort();
+        let mut family = self.descendants(base_form, &mut HashSet::from([base_form]));
+        family.push(base_form);
+        family.sort();
+
+        Some(EvolutionFamily {
+            dex_id,
+            base_form,
+            pre_evolutions,
+            evolutions,
+            family,
+            tree: self.node(base_form, &mut HashSet::new()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn graph() -> EvolutionGraph {
+        EvolutionGraph::new(&[
+            Pokemon::test(133).with_name("Eevee"),
+            Pokemon::test(134).with_name("Vaporeon").with_evolves_from(133),
+            Pokemon::test(135).with_name("Jolteon").with_evolves_from(133),
+            Pokemon::test(43).with_name("Oddish"),
+            Pokemon::test(44).with_name("Gloom").with_evolves_from(43),
+            Pokemon::test(45).with_name("Vileplume").with_evolves_from(44),
+            Pokemon::test(182).with_name("Bellossom").with_evolves_from(44),
+            Pokemon::test(6).with_name("Charizard").with_evolves_from(5),
+            Pokemon::test(25).with_name("Pikachu"),
+        ])
+    }
+
+    #[test]
+    fn test_branching_family() {
+        let graph = graph();
+        let family = graph.family(45).unwrap();
+        assert_eq!(family.base_form, 43);
+        assert_eq!(family.pre_evolutions, vec![43, 44]);
+        assert!(family.evolutions.is_empty());
+        assert_eq!(family.family, vec![43, 44, 45, 182]);
+
+        let gloom = &family.tree.evolves_into[0];
+        assert_eq!(gloom.name, "Gloom");
+        let branches: Vec<u32> = gloom.evolves_into.iter().map(|n| n.dex_id).collect();
+        assert_eq!(branches, vec![45, 182]);
+
+        assert_eq!(graph.family(133).unwrap().evolutions, vec![134, 135]);
+        assert!(graph.same_family(134, 135));
+        assert!(!graph.same_family(134, 25));
+    }
+
+    #[test]
+    fn test_missing_and_single_stage() {
+        let graph = graph();
+        // Charmeleon isn't in this pokedex
+        assert_eq!(graph.base_form(6), 6);
+
+        let pikachu = graph.family(25).unwrap();
+        assert_eq!(pikachu.family, vec![25]);
+        assert_eq!(pikachu.tree, EvolutionNode { dex_id: 25, name: "Pikachu".into(), evolves_into: vec![] });
+        assert!(graph.family(999).is_none());
+    }
+}
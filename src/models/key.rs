@@ -0,0 +1,124 @@
+//! Player keys. A player gets a random uuid as their key when they join, the
+//! database only ever sees a salted HMAC-SHA256 of it.
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const SCHEME: &str = "hmac-sha256";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KeyHash {
+    /// `hmac-sha256$<salt>$<mac>`, salt and mac hex encoded.
+    Hmac(String),
+    /// `DefaultHasher` of the uuid, which is what `draft_user` records stored
+    /// before. Still accepted so players in running sessions aren't locked out,
+    /// and swapped for an `Hmac` the first time the key checks out.
+    Legacy(i64),
+}
+
+impl Default for KeyHash {
+    // never matches anything
+    fn default() -> KeyHash {
+        KeyHash::Hmac(String::new())
+    }
+}
+
+fn mac(salt: &[u8], key: &Uuid) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(salt).expect("hmac takes keys of any length");
+    mac.update(key.as_bytes());
+    mac
+}
+
+fn legacy_hash(key: &Uuid) -> i64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish() as i64
+}
+
+impl KeyHash {
+    pub fn new(key: &Uuid) -> KeyHash {
+        // v4 uuids are 122 random bits, plenty for a salt
+        let salt = Uuid::new_v4();
+        let tag = mac(salt.as_bytes(), key).finalize().into_bytes();
+
+        KeyHash::Hmac(format!("{SCHEME}${}${}", hex::encode(salt.as_bytes()), hex::encode(tag)))
+    }
+
+    /// Checks `key` against the stored hash in constant time.
+    pub fn verify(&self, key: &Uuid) -> bool {
+        match self {
+            KeyHash::Hmac(stored) => {
+                let mut parts = stored.split('$');
+                let (salt, tag) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+                    (Some(SCHEME), Some(salt), Some(tag), None) => (salt, tag),
+                    _ => return false,
+                };
+                let (salt, tag) = match (hex::decode(salt), hex::decode(tag)) {
+                    (Ok(s), Ok(t)) => (s, t),
+                    _ => return false,
+                };
+
+                mac(&salt, key).verify_slice(&tag).is_ok()
+            },
+            KeyHash::Legacy(hash) => {
+                let expected = legacy_hash(key).to_be_bytes();
+                // same as verify_slice, no early exit on the first differing byte
+                expected.iter().zip(hash.to_be_bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+            },
+        }
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        matches!(self, KeyHash::Legacy(_))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rocket::serde::json::serde_json;
+
+    #[test]
+    fn test_hmac_round_trip() {
+        let key = Uuid::new_v4();
+        let hash = KeyHash::new(&key);
+
+        assert!(hash.verify(&key));
+        assert!(!hash.verify(&Uuid::new_v4()));
+        assert!(!hash.is_legacy());
+        // salted, the same key never hashes the same twice
+        assert_ne!(hash, KeyHash::new(&key));
+    }
+
+    #[test]
+    fn test_legacy_hash_still_verifies() {
+        let key = Uuid::new_v4();
+        let hash = KeyHash::Legacy(legacy_hash(&key));
+
+        assert!(hash.verify(&key));
+        assert!(!hash.verify(&Uuid::new_v4()));
+        assert!(hash.is_legacy());
+    }
+
+    #[test]
+    fn test_stored_shapes() {
+        let old: KeyHash = serde_json::from_str("-4242").unwrap();
+        assert_eq!(old, KeyHash::Legacy(-4242));
+
+        let new = KeyHash::new(&Uuid::new_v4());
+        let json = serde_json::to_string(&new).unwrap();
+        assert!(json.starts_with("\"hmac-sha256$"));
+        assert_eq!(serde_json::from_str::<KeyHash>(&json).unwrap(), new);
+
+        assert!(!KeyHash::default().verify(&Uuid::new_v4()));
+        assert!(!KeyHash::Hmac("hmac-sha256$zz$zz".into()).verify(&Uuid::new_v4()));
+    }
+}
@@ -0,0 +1,42 @@
+//! Every pokemon by dex id, along with their evolution graph. Read once when
+//! the server starts and shared, the pokedex only changes through the importer
+//! so a restart picks up a new one.
+use std::collections::HashMap;
+
+use super::evolution::EvolutionGraph;
+use super::pokemon::Pokemon;
+
+#[derive(Debug, Default)]
+pub struct Pokedex {
+    pokemon: HashMap<u32, Pokemon>,
+    evolutions: EvolutionGraph,
+}
+
+impl Pokedex {
+    pub fn new(pokemon: Vec<Pokemon>) -> Pokedex {
+        let evolutions = EvolutionGraph::new(&pokemon);
+        Pokedex {
+            pokemon: pokemon.into_iter().map(|p| (p.dex_id, p)).collect(),
+            evolutions,
+        }
+    }
+
+    pub fn get(&self, dex_id: u32) -> Option<&Pokemon> {
+        self.pokemon.get(&dex_id)
+    }
+
+    /// The pokemon in `picks`, in their order. Picks missing from the pokedex
+    /// are left out.
+    pub fn roster(&self, picks: &[u32]) -> Vec<Pokemon> {
+        picks.iter().filter_map(|pk| self.get(*pk)).cloned().collect()
+    }
+
+    /// Every pokemon, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &Pokemon> {
+        self.pokemon.values()
+    }
+
+    pub fn evolutions(&self) -> &EvolutionGraph {
+        &self.evolutions
+    }
+}
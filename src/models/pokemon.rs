@@ -1,8 +1,14 @@
-use serde::{Deserialize, Serialize};
+use rocket::form::FromFormField;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
 use surrealdb::RecordId;
 
-#[derive(Debug, Serialize, Deserialize)]
-#[warn(dead_code)]
+use super::key::KeyHash;
+
+// Variant names match the upper case strings the importer stores, query
+// strings can use any case
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, FromFormField)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum PokemonType {
     NORMAL,
     FIRE,
@@ -28,26 +34,274 @@ pub enum PokemonType {
 // probably a better way than to make these all public
 // TODO: Serializing theses fields looks a little gross in the frontend
 // I should implement the serialiers myself to make em nicer
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(crate = "rocket::serde")]
 pub struct Pokemon {
+    /// National dex number, or for an alternate form its own id from 10001
+    /// up, the way PokeAPI numbers them. It's what draft sets, picks and bans
+    /// refer to the pokemon by, so forms are drafted apart from their species.
     pub dex_id: u32,
     pub id: Option<RecordId>,
     pub name: String,
     pub type1: PokemonType,
     pub type2: Option<PokemonType>,
+    /// A mega evolves from its base species, a regional form from its own
+    /// line's pre-evolution.
     pub evolves_from: u16,
     pub gen: u8,
     pub is_legendary: bool,
     pub is_mythic: bool,
+    /// Missing for pokedexes imported without them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<BaseStats>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub abilities: Vec<Ability>,
+    /// Set on alternate forms only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub form: Option<PokemonForm>,
+}
+
+/// Serialized with the `bst` total alongside the six stats.
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(crate = "rocket::serde")]
+pub struct BaseStats {
+    pub hp: u16,
+    pub atk: u16,
+    pub def: u16,
+    pub spa: u16,
+    pub spd: u16,
+    pub spe: u16,
+}
+
+impl BaseStats {
+    /// Base stat total.
+    pub fn total(&self) -> u16 {
+        self.hp + self.atk + self.def + self.spa + self.spd + self.spe
+    }
+}
+
+impl Serialize for BaseStats {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut stats = serializer.serialize_struct("BaseStats", 7)?;
+        stats.serialize_field("hp", &self.hp)?;
+        stats.serialize_field("atk", &self.atk)?;
+        stats.serialize_field("def", &self.def)?;
+        stats.serialize_field("spa", &self.spa)?;
+        stats.serialize_field("spd", &self.spd)?;
+        stats.serialize_field("spe", &self.spe)?;
+        stats.serialize_field("bst", &self.total())?;
+        stats.end()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct Ability {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_hidden: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(crate = "rocket::serde")]
+pub enum FormKind {
+    Regional,
+    Mega,
+    Other,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct PokemonForm {
+    /// National dex number of the species this is a form of.
+    pub species: u32,
+    pub kind: FormKind,
+    /// The form's part of the name, e.g. "alola" or "mega-x".
+    pub name: String,
+}
+
+impl Pokemon {
+    /// National dex number, the species' for an alternate form.
+    pub fn species_id(&self) -> u32 {
+        self.form.as_ref().map_or(self.dex_id, |f| f.species)
+    }
+
+    /// One or two types, without the `NONE` a single typed pokemon stores.
+    pub fn types(&self) -> Vec<PokemonType> {
+        let mut types = vec![self.type1];
+        types.extend(self.type2.filter(|t| *t != PokemonType::NONE && *t != self.type1));
+        types
+    }
+}
+
+/// Pokemon for tests, a gen 1 NORMAL type named `#<dex_id>` until told otherwise.
+#[cfg(test)]
+impl Pokemon {
+    pub fn test(dex_id: u32) -> Pokemon {
+        Pokemon {
+            dex_id,
+            id: None,
+            name: format!("#{dex_id}"),
+            type1: PokemonType::NORMAL,
+            type2: None,
+            evolves_from: 0,
+            gen: 1,
+            is_legendary: false,
+            is_mythic: false,
+            stats: None,
+            abilities: vec![],
+            form: None,
+        }
+    }
+
+    pub fn with_name(self, name: &str) -> Pokemon {
+        Pokemon { name: name.to_string(), ..self }
+    }
+
+    pub fn with_type(self, type1: PokemonType) -> Pokemon {
+        Pokemon { type1, ..self }
+    }
+
+    pub fn with_types(self, type1: PokemonType, type2: PokemonType) -> Pokemon {
+        Pokemon { type1, type2: Some(type2), ..self }
+    }
+
+    pub fn with_evolves_from(self, evolves_from: u16) -> Pokemon {
+        Pokemon { evolves_from, ..self }
+    }
+
+    pub fn with_gen(self, gen: u8) -> Pokemon {
+        Pokemon { gen, ..self }
+    }
+
+    pub fn legendary(self) -> Pokemon {
+        Pokemon { is_legendary: true, ..self }
+    }
+
+    pub fn mythic(self) -> Pokemon {
+        Pokemon { is_mythic: true, ..self }
+    }
+
+    pub fn with_ability(mut self, name: &str, is_hidden: bool) -> Pokemon {
+        self.abilities.push(Ability { name: name.to_string(), is_hidden });
+        self
+    }
+}
+
+/// Picks pokemon out of the pokedex for a draft set. Every filter that's set
+/// has to match, then `include` is added and `exclude` taken away.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct PokemonFilter {
+    /// Generations to take pokemon from, all of them when empty.
+    #[serde(default)]
+    pub gens: Vec<u8>,
+    /// Pokemon with at least one of these types, any type when empty.
+    #[serde(default)]
+    pub types: Vec<PokemonType>,
+    #[serde(default)]
+    pub type1: Option<PokemonType>,
+    /// `NONE` for single typed pokemon.
+    #[serde(default)]
+    pub type2: Option<PokemonType>,
+    #[serde(default)]
+    pub is_legendary: Option<bool>,
+    #[serde(default)]
+    pub is_mythic: Option<bool>,
+    /// Only pokemon that don't evolve from anything.
+    #[serde(default)]
+    pub base_only: bool,
+    /// Only alternate forms with true, none of them with false.
+    #[serde(default)]
+    pub forms: Option<bool>,
+    #[serde(default)]
+    pub include: Vec<u32>,
+    #[serde(default)]
+    pub exclude: Vec<u32>,
+}
+
+impl PokemonFilter {
+    pub fn matches(&self, pokemon: &Pokemon) -> bool {
+        (self.gens.is_empty() || self.gens.contains(&pokemon.gen))
+            && (self.types.is_empty() || pokemon.types().iter().any(|t| self.types.contains(t)))
+            && self.type1.map_or(true, |t| pokemon.type1 == t)
+            && self.type2.map_or(true, |t| pokemon.type2.unwrap_or(PokemonType::NONE) == t)
+            && self.is_legendary.map_or(true, |l| pokemon.is_legendary == l)
+            && self.is_mythic.map_or(true, |m| pokemon.is_mythic == m)
+            && (!self.base_only || pokemon.evolves_from == 0)
+            && self.forms.map_or(true, |f| pokemon.form.is_some() == f)
+    }
+
+    /// Dex ids of the matching pokemon, in dex order.
+    pub fn select<'a>(&self, pokedex: impl IntoIterator<Item = &'a Pokemon>) -> Vec<u32> {
+        let mut ids: Vec<u32> = pokedex
+            .into_iter()
+            .filter(|p| self.matches(p) || self.include.contains(&p.dex_id))
+            .map(|p| p.dex_id)
+            .filter(|id| !self.exclude.contains(id))
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct PokemonDraftSet {
-    id: Option<RecordId>,
-    name: String,
-    pokemon: Option<PokemonResponse>,
+    pub id: Option<RecordId>,
+    pub name: String,
+    pub pokemon: Option<PokemonResponse>,
+    /// What the set says about each member, alongside `pokemon`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<DraftSetMember>>,
+    /// Hash of the token handed out when the set was created, never sent back.
+    /// Sets the importer made have none and can't be changed through the API.
+    #[serde(default, skip_serializing)]
+    pub owner_key_hash: Option<KeyHash>,
+}
+
+/// A pokemon in a draft set. The fields past `dex_id` live on the `contains`
+/// edge, so the same pokemon can be worth something different in another set.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(crate = "rocket::serde", from = "SetMemberRepr")]
+pub struct DraftSetMember {
+    pub dex_id: u32,
+    /// What picking it costs against `point_budget`, nothing when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<u32>,
+    /// Named tier, e.g. "OU" or "Tier 1", that `tier_limits` count picks by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tier: Option<String>,
+}
+
+impl DraftSetMember {
+    pub fn new(dex_id: u32) -> DraftSetMember {
+        DraftSetMember { dex_id, points: None, tier: None }
+    }
+}
+
+// Pools stored before members carried anything were bare dex ids
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde", untagged)]
+enum SetMemberRepr {
+    DexId(u32),
+    Member {
+        dex_id: u32,
+        #[serde(default)]
+        points: Option<u32>,
+        #[serde(default)]
+        tier: Option<String>,
+    },
+}
+
+impl From<SetMemberRepr> for DraftSetMember {
+    fn from(repr: SetMemberRepr) -> DraftSetMember {
+        match repr {
+            SetMemberRepr::DexId(dex_id) => DraftSetMember::new(dex_id),
+            SetMemberRepr::Member { dex_id, points, tier } => DraftSetMember { dex_id, points, tier },
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -56,3 +310,69 @@ pub enum PokemonResponse {
     Ids(Vec<u32>),
     Stats(Vec<Pokemon>),
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rocket::serde::json::serde_json;
+
+    #[test]
+    fn test_filter_select() {
+        let pokedex = vec![
+            Pokemon::test(4).with_type(PokemonType::FIRE),
+            Pokemon::test(5).with_type(PokemonType::FIRE).with_evolves_from(4),
+            Pokemon::test(7).with_type(PokemonType::WATER),
+            Pokemon::test(155).with_type(PokemonType::FIRE).with_gen(2),
+            Pokemon::test(146).with_type(PokemonType::FIRE).legendary(),
+        ];
+
+        let filter = PokemonFilter { types: vec![PokemonType::FIRE], gens: vec![1], ..Default::default() };
+        assert_eq!(filter.select(&pokedex), vec![4, 5, 146]);
+
+        let filter = PokemonFilter {
+            base_only: true,
+            is_legendary: Some(false),
+            include: vec![5],
+            exclude: vec![155],
+            ..Default::default()
+        };
+        assert_eq!(filter.select(&pokedex), vec![4, 5, 7]);
+        assert_eq!(PokemonFilter::default().select(&pokedex).len(), 5);
+
+        let charizard = Pokemon::test(6).with_types(PokemonType::FIRE, PokemonType::FLYING).with_evolves_from(5);
+        let pokedex = vec![Pokemon::test(4).with_type(PokemonType::FIRE), charizard];
+        let filter = PokemonFilter { type2: Some(PokemonType::NONE), ..Default::default() };
+        assert_eq!(filter.select(&pokedex), vec![4]);
+        let filter = PokemonFilter { type1: Some(PokemonType::FLYING), ..Default::default() };
+        assert!(filter.select(&pokedex).is_empty());
+    }
+
+    #[test]
+    fn test_forms_and_stats() {
+        let stats = BaseStats { hp: 78, atk: 130, def: 111, spa: 130, spd: 85, spe: 100 };
+        let mega = Pokemon {
+            stats: Some(stats),
+            form: Some(PokemonForm { species: 6, kind: FormKind::Mega, name: "mega-x".into() }),
+            ..Pokemon::test(10034)
+                .with_types(PokemonType::FIRE, PokemonType::DRAGON)
+                .with_evolves_from(6)
+                .with_gen(6)
+                .with_ability("Tough Claws", false)
+        };
+        assert_eq!(mega.species_id(), 6);
+        assert_eq!(Pokemon::test(6).with_type(PokemonType::FIRE).with_evolves_from(5).species_id(), 6);
+
+        let json = serde_json::to_value(&mega).unwrap();
+        assert_eq!(json["stats"]["bst"], 634);
+        assert_eq!(json["abilities"], serde_json::json!([{"name": "Tough Claws"}]));
+        let back: Pokemon = serde_json::from_value(json).unwrap();
+        assert_eq!(back.stats, Some(stats));
+        assert_eq!(back.form, mega.form);
+
+        let pokedex = vec![Pokemon::test(6).with_type(PokemonType::FIRE).with_evolves_from(5), mega];
+        let filter = PokemonFilter { forms: Some(true), ..Default::default() };
+        assert_eq!(filter.select(&pokedex), vec![10034]);
+        let filter = PokemonFilter { forms: Some(false), ..Default::default() };
+        assert_eq!(filter.select(&pokedex), vec![6]);
+    }
+}
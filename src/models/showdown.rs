@@ -0,0 +1,77 @@
+//! Rosters as text Pokemon Showdown's teambuilder can import, so players don't
+//! have to retype their picks once the draft is over.
+use super::pokemon::Pokemon;
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Showdown matches species ignoring case and punctuation, PokeAPI style names
+/// like "charizard-mega-x" only need capitalizing to read right.
+fn species_name(pokemon: &Pokemon) -> String {
+    pokemon.name.split('-').map(capitalize).collect::<Vec<String>>().join("-")
+}
+
+/// The first ability that isn't hidden, "solar-power" spelled "Solar Power".
+fn ability_name(pokemon: &Pokemon) -> Option<String> {
+    let ability = pokemon.abilities.iter().find(|a| !a.is_hidden)?;
+    Some(ability.name.split(['-', ' ']).map(capitalize).collect::<Vec<String>>().join(" "))
+}
+
+/// One set per pokemon, with just the species and ability. Showdown fills in
+/// the rest with its defaults.
+pub fn team(roster: &[Pokemon]) -> String {
+    roster
+        .iter()
+        .map(|p| match ability_name(p) {
+            Some(ability) => format!("{}\nAbility: {ability}\n", species_name(p)),
+            None => format!("{}\n", species_name(p)),
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Every team under its player's name, the way Showdown backs up and imports
+/// a whole teambuilder at once.
+pub fn league(teams: &[(String, Vec<Pokemon>)]) -> String {
+    teams
+        .iter()
+        .map(|(name, roster)| format!("=== {name} ===\n\n{}", team(roster)))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_team() {
+        let roster = vec![
+            Pokemon::test(10034).with_name("charizard-mega-x").with_ability("tough-claws", false),
+            Pokemon::test(7).with_name("Squirtle").with_ability("Rain Dish", true).with_ability("Torrent", false),
+            Pokemon::test(4).with_name("charmander"),
+        ];
+        assert_eq!(
+            team(&roster),
+            "Charizard-Mega-X\nAbility: Tough Claws\n\nSquirtle\nAbility: Torrent\n\nCharmander\n"
+        );
+        assert_eq!(team(&[]), "");
+    }
+
+    #[test]
+    fn test_league() {
+        let teams = vec![
+            ("Player 1".to_string(), vec![Pokemon::test(4).with_name("charmander")]),
+            ("Player 2".to_string(), vec![Pokemon::test(7).with_name("squirtle"), Pokemon::test(1).with_name("bulbasaur")]),
+        ];
+        assert_eq!(
+            league(&teams),
+            "=== Player 1 ===\n\nCharmander\n\n=== Player 2 ===\n\nSquirtle\n\nBulbasaur\n"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! Type effectiveness, as of gen 6, and what it means for a whole roster: which
+//! attacking types it's weak to or walls, and which types its STAB moves hit
+//! super effectively.
+use serde::Serialize;
+
+use super::pokemon::{Pokemon, PokemonType};
+use PokemonType::*;
+
+/// Every type a pokemon or move can have, `NONE` left out.
+pub const ALL_TYPES: [PokemonType; 18] = [
+    NORMAL, FIRE, WATER, ELECTRIC, GRASS, ICE, FIGHTING, POISON, GROUND,
+    FLYING, PSYCHIC, BUG, ROCK, GHOST, DRAGON, DARK, STEEL, FAIRY,
+];
+
+/// Types `attack` is super effective against, not very effective against, and
+/// can't touch.
+fn matchups(attack: PokemonType) -> (&'static [PokemonType], &'static [PokemonType], &'static [PokemonType]) {
+    match attack {
+        NORMAL => (&[], &[ROCK, STEEL], &[GHOST]),
+        FIRE => (&[GRASS, ICE, BUG, STEEL], &[FIRE, WATER, ROCK, DRAGON], &[]),
+        WATER => (&[FIRE, GROUND, ROCK], &[WATER, GRASS, DRAGON], &[]),
+        ELECTRIC => (&[WATER, FLYING], &[ELECTRIC, GRASS, DRAGON], &[GROUND]),
+        GRASS => (&[WATER, GROUND, ROCK], &[FIRE, GRASS, POISON, FLYING, BUG, DRAGON, STEEL], &[]),
+        ICE => (&[GRASS, GROUND, FLYING, DRAGON], &[FIRE, WATER, ICE, STEEL], &[]),
+        FIGHTING => (&[NORMAL, ICE, ROCK, DARK, STEEL], &[POISON, FLYING, PSYCHIC, BUG, FAIRY], &[GHOST]),
+        POISON => (&[GRASS, FAIRY], &[POISON, GROUND, ROCK, GHOST], &[STEEL]),
+        GROUND => (&[FIRE, ELECTRIC, POISON, ROCK, STEEL], &[GRASS, BUG], &[FLYING]),
+        FLYING => (&[GRASS, FIGHTING, BUG], &[ELECTRIC, ROCK, STEEL], &[]),
+        PSYCHIC => (&[FIGHTING, POISON], &[PSYCHIC, STEEL], &[DARK]),
+        BUG => (&[GRASS, PSYCHIC, DARK], &[FIRE, FIGHTING, POISON, FLYING, GHOST, STEEL, FAIRY], &[]),
+        ROCK => (&[FIRE, ICE, FLYING, BUG], &[FIGHTING, GROUND, STEEL], &[]),
+        GHOST => (&[PSYCHIC, GHOST], &[DARK], &[NORMAL]),
+        DRAGON => (&[DRAGON], &[STEEL], &[FAIRY]),
+        DARK => (&[PSYCHIC, GHOST], &[FIGHTING, DARK, FAIRY], &[]),
+        STEEL => (&[ICE, ROCK, FAIRY], &[FIRE, WATER, ELECTRIC, STEEL], &[]),
+        FAIRY => (&[FIGHTING, DRAGON, DARK], &[FIRE, POISON, STEEL], &[]),
+        NONE => (&[], &[], &[]),
+    }
+}
+
+/// How much damage a move of type `attack` does to a pokemon of type `defend`.
+pub fn attack_multiplier(attack: PokemonType, defend: PokemonType) -> f32 {
+    let (strong, weak, immune) = matchups(attack);
+    if strong.contains(&defend) {
+        2.0
+    } else if weak.contains(&defend) {
+        0.5
+    } else if immune.contains(&defend) {
+        0.0
+    } else {
+        1.0
+    }
+}
+
+/// How much damage a move of type `attack` does to a pokemon with both of `types`.
+pub fn defense_multiplier(attack: PokemonType, types: &[PokemonType]) -> f32 {
+    types.iter().map(|t| attack_multiplier(attack, *t)).product()
+}
+
+/// How the roster takes hits of one type.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct DefensiveMatchup {
+    #[serde(rename = "type")]
+    pub attack_type: PokemonType,
+    pub weak: Vec<u32>,
+    /// Takes less than neutral damage, immunities aside.
+    pub resist: Vec<u32>,
+    pub immune: Vec<u32>,
+}
+
+/// Who on the roster has a STAB type that hits one type super effectively.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct OffensiveMatchup {
+    #[serde(rename = "type")]
+    pub defend_type: PokemonType,
+    pub super_effective: Vec<u32>,
+}
+
+/// Pokemon are listed by dex id in every matchup.
+#[derive(Debug, Serialize)]
+pub struct TeamCoverage {
+    pub pokemon: Vec<u32>,
+    /// One entry per attacking type.
+    pub defense: Vec<DefensiveMatchup>,
+    /// One entry per defending type.
+    pub offense: Vec<OffensiveMatchup>,
+    /// Attacking types more of the roster is weak to than resists or is immune to.
+    pub weaknesses: Vec<PokemonType>,
+    /// Types nothing on the roster hits super effectively with its STAB.
+    pub uncovered: Vec<PokemonType>,
+}
+
+impl TeamCoverage {
+    pub fn new(roster: &[Pokemon]) -> TeamCoverage {
+        let defense: Vec<DefensiveMatchup> = ALL_TYPES
+            .iter()
+            .map(|attack| {
+                let taking = |keep: fn(f32) -> bool| -> Vec<u32> {
+                    roster
+                        .iter()
+                        .filter(|p| keep(defense_multiplier(*attack, &p.types())))
+                        .map(|p| p.dex_id)
+                        .collect()
+                };
+                DefensiveMatchup {
+                    attack_type: *attack,
+                    weak: taking(|m| m > 1.0),
+                    resist: taking(|m| m > 0.0 && m < 1.0),
+                    immune: taking(|m| m == 0.0),
+                }
+            })
+            .collect();
+
+        let offense: Vec<OffensiveMatchup> = ALL_TYPES
+            .iter()
+            .map(|defend| OffensiveMatchup {
+                defend_type: *defend,
+                super_effective: roster
+                    .iter()
+                    .filter(|p| p.types().iter().any(|stab| attack_multiplier(*stab, *defend) > 1.0))
+                    .map(|p| p.dex_id)
+                    .collect(),
+            })
+            .collect();
+
+        TeamCoverage {
+            pokemon: roster.iter().map(|p| p.dex_id).collect(),
+            weaknesses: defense
+                .iter()
+                .filter(|d| d.weak.len() > d.resist.len() + d.immune.len())
+                .map(|d| d.attack_type)
+                .collect(),
+            uncovered: offense
+                .iter()
+                .filter(|o| o.super_effective.is_empty())
+                .map(|o| o.defend_type)
+                .collect(),
+            defense,
+            offense,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_multipliers() {
+        assert_eq!(attack_multiplier(WATER, FIRE), 2.0);
+        assert_eq!(attack_multiplier(FIRE, WATER), 0.5);
+        assert_eq!(attack_multiplier(NORMAL, GHOST), 0.0);
+        assert_eq!(attack_multiplier(DRAGON, FAIRY), 0.0);
+        assert_eq!(attack_multiplier(PSYCHIC, NORMAL), 1.0);
+
+        // Charizard
+        assert_eq!(defense_multiplier(ROCK, &[FIRE, FLYING]), 4.0);
+        assert_eq!(defense_multiplier(GROUND, &[FIRE, FLYING]), 0.0);
+        assert_eq!(defense_multiplier(GRASS, &[FIRE, FLYING]), 0.25);
+        assert_eq!(defense_multiplier(WATER, &[FIRE, FLYING]), 2.0);
+        // Venusaur
+        assert_eq!(defense_multiplier(WATER, &[GRASS, POISON]), 0.5);
+
+        // every attacking type is 2x, 1x, 0.5x or 0x against every other
+        for attack in ALL_TYPES {
+            for defend in ALL_TYPES {
+                assert!([0.0, 0.5, 1.0, 2.0].contains(&attack_multiplier(attack, defend)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_team_coverage() {
+        let roster = vec![
+            Pokemon::test(6).with_types(FIRE, FLYING),
+            Pokemon::test(9).with_type(WATER),
+        ];
+        let coverage = TeamCoverage::new(&roster);
+
+        let electric = coverage.defense.iter().find(|d| d.attack_type == ELECTRIC).unwrap();
+        assert_eq!(electric, &DefensiveMatchup { attack_type: ELECTRIC, weak: vec![6, 9], resist: vec![], immune: vec![] });
+        let ground = coverage.defense.iter().find(|d| d.attack_type == GROUND).unwrap();
+        assert_eq!(ground.immune, vec![6]);
+        assert!(ground.resist.is_empty());
+
+        assert_eq!(coverage.weaknesses, vec![ELECTRIC, ROCK]);
+
+        let grass = coverage.offense.iter().find(|o| o.defend_type == GRASS).unwrap();
+        assert_eq!(grass.super_effective, vec![6]);
+        assert!(!coverage.uncovered.contains(&ROCK));
+        assert!(coverage.uncovered.contains(&NORMAL));
+        assert!(coverage.uncovered.contains(&ELECTRIC));
+    }
+}
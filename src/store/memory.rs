@@ -0,0 +1,358 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::draft::history::DraftEventRecord;
+use crate::models::draft::{DraftRules, DraftSession, DraftState, DraftUser, PlayerUpdate};
+use crate::models::key::KeyHash;
+use crate::models::pokemon::{DraftSetMember, Pokemon, PokemonDraftSet, PokemonResponse};
+use crate::store::{
+    DraftStore, StoreError, StoreResult, DRAFT_EVENT_TB, DRAFT_RULES_TB, DRAFT_SESSION_TB,
+    DRAFT_SET_TB, DRAFT_USER_TB, POKEMON_TB,
+};
+
+use rocket::serde::json::serde_json;
+use rocket::tokio::sync::RwLock;
+
+use serde::Deserialize;
+
+use surrealdb::RecordId;
+
+use uuid::Uuid;
+
+/// Keeps everything in process. Nothing survives a restart, which is the point:
+/// tests and local demos can run the whole API without a SurrealDB server.
+#[derive(Default)]
+pub struct MemoryStore {
+    data: RwLock<MemoryData>,
+}
+
+#[derive(Default)]
+struct MemoryData {
+    pokemon: BTreeMap<u32, Pokemon>,
+    draft_sets: Vec<MemoryDraftSet>,
+    draft_rules: Vec<DraftRules>,
+    sessions: HashMap<RecordId, DraftSession>,
+    users: HashMap<RecordId, DraftUser>,
+    // draft_session -> draft_user, the `players` relation
+    players: HashMap<RecordId, Vec<RecordId>>,
+    events: Vec<DraftEventRecord>,
+}
+
+struct MemoryDraftSet {
+    id: RecordId,
+    name: String,
+    pokemon: Vec<DraftSetMember>,
+    owner_key_hash: Option<KeyHash>,
+}
+
+/// Shape of the file passed in as `memory_seed`.
+#[derive(Deserialize)]
+struct Seed {
+    #[serde(default)]
+    pokemon: Vec<Pokemon>,
+    #[serde(default)]
+    draft_sets: Vec<SeedDraftSet>,
+    #[serde(default)]
+    draft_rules: Vec<DraftRules>,
+}
+
+#[derive(Deserialize)]
+struct SeedDraftSet {
+    name: String,
+    /// Bare dex ids, or `{"dex_id": .., "points": ..}`.
+    pokemon: Vec<DraftSetMember>,
+}
+
+// RecordIdKey has no accessor for the string inside, its serialized form does
+fn key_of(id: &RecordId) -> Option<String> {
+    serde_json::to_value(id).ok()?["id"]["String"].as_str().map(String::from)
+}
+
+fn new_id(table: &str) -> RecordId {
+    RecordId::from_table_key(table, Uuid::new_v4().simple().to_string())
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+
+    pub async fn from_seed_file(path: &str) -> Result<MemoryStore, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| format!("Unable to read {path}: {e}"))?;
+        let seed: Seed = serde_json::from_str(&raw).map_err(|e| format!("Unable to parse {path}: {e}"))?;
+
+        let store = MemoryStore::new();
+        for pokemon in seed.pokemon {
+            store.insert_pokemon(pokemon).await;
+        }
+        for set in seed.draft_sets {
+            store.insert_draft_set(&set.name, set.pokemon).await;
+        }
+        for rules in seed.draft_rules {
+            store.insert_draft_rules(rules).await;
+        }
+
+        Ok(store)
+    }
+
+    pub async fn insert_pokemon(&self, mut pokemon: Pokemon) {
+        pokemon.id = Some(RecordId::from_table_key(POKEMON_TB, pokemon.dex_id.to_string()));
+        self.data.write().await.pokemon.insert(pokemon.dex_id, pokemon);
+    }
+
+    pub async fn insert_draft_set(&self, name: &str, pokemon: Vec<DraftSetMember>) -> RecordId {
+        let id = new_id(DRAFT_SET_TB);
+        self.data.write().await.draft_sets.push(MemoryDraftSet {
+            id: id.clone(),
+            name: name.to_string(),
+            pokemon,
+            owner_key_hash: None,
+        });
+        id
+    }
+
+    pub async fn insert_draft_rules(&self, mut rules: DraftRules) -> RecordId {
+        let id = new_id(DRAFT_RULES_TB);
+        rules.id = Some(id.clone());
+        self.data.write().await.draft_rules.push(rules);
+        id
+    }
+}
+
+#[rocket::async_trait]
+impl DraftStore for MemoryStore {
+    async fn get_pokemon(&self, id: &str) -> StoreResult<Option<Pokemon>> {
+        let dex_id: u32 = match id.parse() {
+            Ok(d) => d,
+            Err(_) => return Ok(None),
+        };
+
+        Ok(self.data.read().await.pokemon.get(&dex_id).cloned())
+    }
+
+    async fn list_pokemon(&self) -> StoreResult<Vec<Pokemon>> {
+        Ok(self.data.read().await.pokemon.values().cloned().collect())
+    }
+
+    async fn list_draft_sets(&self) -> StoreResult<Vec<PokemonDraftSet>> {
+        let data = self.data.read().await;
+        let sets = data
+            .draft_sets
+            .iter()
+            .map(|s| PokemonDraftSet {
+                id: Some(s.id.clone()),
+                name: s.name.clone(),
+                pokemon: None,
+                members: None,
+                owner_key_hash: s.owner_key_hash.clone(),
+            })
+            .collect();
+
+        Ok(sets)
+    }
+
+    async fn get_draft_set(&self, id: &str, detailed: bool) -> StoreResult<Option<PokemonDraftSet>> {
+        let id = RecordId::from_table_key(DRAFT_SET_TB, id);
+        let data = self.data.read().await;
+        let set = match data.draft_sets.iter().find(|s| s.id == id) {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+
+        // only members that actually exist, same as following the `contains` edges
+        let mut members: Vec<&DraftSetMember> = set.pokemon
+            .iter()
+            .filter(|m| data.pokemon.contains_key(&m.dex_id))
+            .collect();
+        members.sort_by_key(|m| m.dex_id);
+
+        let pokemon = if !detailed {
+            PokemonResponse::Ids(members.iter().map(|m| m.dex_id).collect())
+        } else {
+            PokemonResponse::Stats(members.iter().filter_map(|m| data.pokemon.get(&m.dex_id)).cloned().collect())
+        };
+
+        Ok(Some(PokemonDraftSet {
+            id: Some(set.id.clone()),
+            name: set.name.clone(),
+            pokemon: Some(pokemon),
+            members: Some(members.into_iter().cloned().collect()),
+            owner_key_hash: set.owner_key_hash.clone(),
+        }))
+    }
+
+    async fn create_draft_set(
+        &self,
+        name: &str,
+        members: Vec<DraftSetMember>,
+        owner_key_hash: KeyHash,
+    ) -> StoreResult<PokemonDraftSet> {
+        let id = self.insert_draft_set(name, vec![]).await;
+        let key = key_of(&id).ok_or(StoreError::MissingId(DRAFT_SET_TB))?;
+        if let Some(set) = self.data.write().await.draft_sets.iter_mut().find(|s| s.id == id) {
+            set.owner_key_hash = Some(owner_key_hash);
+        }
+        self.add_draft_set_members(&key, members).await?;
+
+        self.get_draft_set(&key, false).await?.ok_or(StoreError::NotCreated(DRAFT_SET_TB))
+    }
+
+    async fn rename_draft_set(&self, id: &str, name: &str) -> StoreResult<()> {
+        let id = RecordId::from_table_key(DRAFT_SET_TB, id);
+        if let Some(set) = self.data.write().await.draft_sets.iter_mut().find(|s| s.id == id) {
+            set.name = name.to_string();
+        }
+        Ok(())
+    }
+
+    async fn add_draft_set_members(&self, id: &str, members: Vec<DraftSetMember>) -> StoreResult<()> {
+        let id = RecordId::from_table_key(DRAFT_SET_TB, id);
+        if let Some(set) = self.data.write().await.draft_sets.iter_mut().find(|s| s.id == id) {
+            set.pokemon.retain(|m| !members.iter().any(|n| n.dex_id == m.dex_id));
+            set.pokemon.extend(members);
+        }
+        Ok(())
+    }
+
+    async fn remove_draft_set_members(&self, id: &str, dex_ids: Vec<u32>) -> StoreResult<()> {
+        let id = RecordId::from_table_key(DRAFT_SET_TB, id);
+        if let Some(set) = self.data.write().await.draft_sets.iter_mut().find(|s| s.id == id) {
+            set.pokemon.retain(|m| !dex_ids.contains(&m.dex_id));
+        }
+        Ok(())
+    }
+
+    async fn delete_draft_set(&self, id: &str) -> StoreResult<()> {
+        let id = RecordId::from_table_key(DRAFT_SET_TB, id);
+        self.data.write().await.draft_sets.retain(|s| s.id != id);
+        Ok(())
+    }
+
+    async fn get_draft_rules(&self, id: &str) -> StoreResult<Option<DraftRules>> {
+        let id = RecordId::from_table_key(DRAFT_RULES_TB, id);
+        let data = self.data.read().await;
+
+        Ok(data.draft_rules.iter().find(|r| r.id.as_ref() == Some(&id)).cloned())
+    }
+
+    async fn list_draft_rules(&self) -> StoreResult<Vec<DraftRules>> {
+        Ok(self.data.read().await.draft_rules.clone())
+    }
+
+    async fn create_draft_rules(&self, mut rules: DraftRules) -> StoreResult<DraftRules> {
+        rules.id = Some(new_id(DRAFT_RULES_TB));
+        self.data.write().await.draft_rules.push(rules.clone());
+        Ok(rules)
+    }
+
+    async fn get_session(&self, id: &str) -> StoreResult<Option<DraftSession>> {
+        let id = RecordId::from_table_key(DRAFT_SESSION_TB, id);
+        let data = self.data.read().await;
+        let mut session = match data.sessions.get(&id) {
+            Some(s) => s.clone(),
+            None => return Ok(None),
+        };
+
+        let mut players: Vec<DraftUser> = data
+            .players
+            .get(&id)
+            .map(|ids| ids.iter().filter_map(|u| data.users.get(u)).cloned().collect())
+            .unwrap_or_default();
+        players.sort_by_key(|p| p.order_in_session);
+        session.players = Some(players);
+
+        Ok(Some(session))
+    }
+
+    async fn sessions_past_deadline(&self, now: u64) -> StoreResult<Vec<String>> {
+        let data = self.data.read().await;
+        let ids = data
+            .sessions
+            .iter()
+            .filter(|(_, s)| s.draft_state == DraftState::InProgress)
+            .filter(|(_, s)| s.turn_deadline.is_some_and(|d| d <= now))
+            .filter_map(|(id, _)| key_of(id))
+            .collect();
+        Ok(ids)
+    }
+
+    async fn create_session(&self, mut session: DraftSession) -> StoreResult<DraftSession> {
+        let id = new_id(DRAFT_SESSION_TB);
+        session.id = Some(id.clone());
+        session.players = None;
+        self.data.write().await.sessions.insert(id, session.clone());
+        Ok(session)
+    }
+
+    async fn update_session_if(&self, session: &DraftSession, action_count: u32) -> StoreResult<bool> {
+        let id = session.id.clone().ok_or(StoreError::MissingId(DRAFT_SESSION_TB))?;
+        let mut data = session.clone();
+        data.players = None;
+
+        let mut store = self.data.write().await;
+        if store.sessions.get(&id).is_some_and(|s| s.action_count != action_count) {
+            return Ok(false);
+        }
+        store.sessions.insert(id, data);
+        Ok(true)
+    }
+
+    async fn create_user(&self, session_id: &str, mut user: DraftUser) -> StoreResult<DraftUser> {
+        let session_id = RecordId::from_table_key(DRAFT_SESSION_TB, session_id);
+        let user_id = user.id.clone().unwrap_or_else(|| new_id(DRAFT_USER_TB));
+        user.id = Some(user_id.clone());
+
+        let mut data = self.data.write().await;
+        data.users.insert(user_id.clone(), user.clone());
+        data.players.entry(session_id).or_default().push(user_id);
+
+        Ok(user)
+    }
+
+    async fn update_user(&self, user_id: &RecordId, update: &PlayerUpdate) -> StoreResult<()> {
+        if let Some(user) = self.data.write().await.users.get_mut(user_id) {
+            user.apply(update);
+        }
+        Ok(())
+    }
+
+    async fn set_pick_queue(&self, user_id: &RecordId, queue: &[u32]) -> StoreResult<()> {
+        if let Some(user) = self.data.write().await.users.get_mut(user_id) {
+            user.set_pick_queue(queue.to_vec());
+        }
+        Ok(())
+    }
+
+    async fn set_key_hash(&self, user_id: &RecordId, key_hash: &KeyHash) -> StoreResult<()> {
+        if let Some(user) = self.data.write().await.users.get_mut(user_id) {
+            user.set_key_hash(key_hash.clone());
+        }
+        Ok(())
+    }
+
+    async fn remove_user(&self, user_id: &RecordId) -> StoreResult<()> {
+        let mut data = self.data.write().await;
+        data.users.remove(user_id);
+        for players in data.players.values_mut() {
+            players.retain(|p| p != user_id);
+        }
+        Ok(())
+    }
+
+    async fn add_draft_event(&self, mut event: DraftEventRecord) -> StoreResult<()> {
+        event.id = Some(new_id(DRAFT_EVENT_TB));
+        self.data.write().await.events.push(event);
+        Ok(())
+    }
+
+    async fn list_draft_events(&self, session_id: &str) -> StoreResult<Vec<DraftEventRecord>> {
+        let session_id = RecordId::from_table_key(DRAFT_SESSION_TB, session_id);
+        let mut events: Vec<DraftEventRecord> = self.data.read().await
+            .events
+            .iter()
+            .filter(|e| e.session == session_id)
+            .cloned()
+            .collect();
+        events.sort_by_key(|e| e.seq);
+        Ok(events)
+    }
+}
@@ -0,0 +1,150 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::models::draft::history::DraftEventRecord;
+use crate::models::draft::{DraftRules, DraftSession, DraftUser, PlayerUpdate};
+use crate::models::key::KeyHash;
+use crate::models::pokemon::{DraftSetMember, Pokemon, PokemonDraftSet};
+
+use surrealdb::RecordId;
+
+pub mod memory;
+pub mod surreal;
+
+pub use memory::MemoryStore;
+pub use surreal::SurrealStore;
+
+pub const POKEMON_TB: &str = "pokemon";
+pub const DRAFT_SET_TB: &str = "pokemon_draft_set";
+pub const DRAFT_RULES_TB: &str = "draft_rules";
+pub const DRAFT_SESSION_TB: &str = "draft_session";
+pub const DRAFT_USER_TB: &str = "draft_user";
+pub const DRAFT_EVENT_TB: &str = "draft_event";
+pub const DRAFT_USER_RELATION: &str = "players";
+pub const DRAFT_SET_RELATION: &str = "contains";
+
+/// The store handed to every route through Rocket's managed state.
+pub type Store = Arc<dyn DraftStore>;
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+#[derive(Debug)]
+pub enum StoreError {
+    Database(String),
+    NotCreated(&'static str),
+    MissingId(&'static str),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Database(e) => write!(f, "{e}"),
+            StoreError::NotCreated(tb) => write!(f, "Could not create {tb} record"),
+            StoreError::MissingId(tb) => write!(f, "Can't update a {tb} record without an id"),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+/// Everything the API needs to persist. Ids taken as `&str` are the record keys
+/// that come in through the route paths, e.g. `draft_session/<id>`.
+#[rocket::async_trait]
+pub trait DraftStore: Send + Sync {
+    async fn get_pokemon(&self, id: &str) -> StoreResult<Option<Pokemon>>;
+    async fn list_pokemon(&self) -> StoreResult<Vec<Pokemon>>;
+
+    async fn list_draft_sets(&self) -> StoreResult<Vec<PokemonDraftSet>>;
+    async fn get_draft_set(&self, id: &str, detailed: bool) -> StoreResult<Option<PokemonDraftSet>>;
+    /// Returns the new set the way `get_draft_set` does without `detailed`.
+    async fn create_draft_set(
+        &self,
+        name: &str,
+        members: Vec<DraftSetMember>,
+        owner_key_hash: KeyHash,
+    ) -> StoreResult<PokemonDraftSet>;
+    async fn rename_draft_set(&self, id: &str, name: &str) -> StoreResult<()>;
+    /// Relates each member to the set, replacing the points and tier of any
+    /// that were already in it.
+    async fn add_draft_set_members(&self, id: &str, members: Vec<DraftSetMember>) -> StoreResult<()>;
+    async fn remove_draft_set_members(&self, id: &str, dex_ids: Vec<u32>) -> StoreResult<()>;
+    /// Deletes the set and its `contains` edges. Sessions keep the pool they
+    /// resolved from it.
+    async fn delete_draft_set(&self, id: &str) -> StoreResult<()>;
+
+    async fn get_draft_rules(&self, id: &str) -> StoreResult<Option<DraftRules>>;
+    async fn list_draft_rules(&self) -> StoreResult<Vec<DraftRules>>;
+    async fn create_draft_rules(&self, rules: DraftRules) -> StoreResult<DraftRules>;
+
+    /// Returns the session with `players` filled in, ordered by `order_in_session`.
+    async fn get_session(&self, id: &str) -> StoreResult<Option<DraftSession>>;
+    async fn create_session(&self, session: DraftSession) -> StoreResult<DraftSession>;
+    /// Writes every field of the session except `players`, but only while the
+    /// stored session is still at `action_count`. Returns whether it was written,
+    /// false means another action got there first and this one is stale.
+    async fn update_session_if(&self, session: &DraftSession, action_count: u32) -> StoreResult<bool>;
+    /// Keys of the in progress sessions whose `turn_deadline` is at or before `now`.
+    async fn sessions_past_deadline(&self, now: u64) -> StoreResult<Vec<String>>;
+
+    /// Creates the user, under its own id if it has one, and relates it to the
+    /// session as one of its players.
+    async fn create_user(&self, session_id: &str, user: DraftUser) -> StoreResult<DraftUser>;
+    /// Writes just the fields in `update`.
+    async fn update_user(&self, user_id: &RecordId, update: &PlayerUpdate) -> StoreResult<()>;
+    /// Writes the user's pick queue and nothing else.
+    async fn set_pick_queue(&self, user_id: &RecordId, queue: &[u32]) -> StoreResult<()>;
+    /// Writes the user's key hash and nothing else.
+    async fn set_key_hash(&self, user_id: &RecordId, key_hash: &KeyHash) -> StoreResult<()>;
+    /// Deletes the user along with its place in the session.
+    async fn remove_user(&self, user_id: &RecordId) -> StoreResult<()>;
+
+    async fn add_draft_event(&self, event: DraftEventRecord) -> StoreResult<()>;
+    /// The session's `draft_event` records, ordered by `seq`.
+    async fn list_draft_events(&self, session_id: &str) -> StoreResult<Vec<DraftEventRecord>>;
+}
+
+/// Members of the draft set `id`, following its `contains` edges. `None` when
+/// the set doesn't exist.
+pub async fn draft_set_members(db: &Store, id: &str) -> StoreResult<Option<Vec<DraftSetMember>>> {
+    let set = match db.get_draft_set(id, false).await? {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+
+    Ok(Some(set.members.unwrap_or_default()))
+}
+
+/// Fills in `draft_pool` for sessions created before it was stored. The caller
+/// saves the session.
+pub async fn load_draft_pool(db: &Store, session: &mut DraftSession) -> StoreResult<()> {
+    if session.draft_pool.is_some() {
+        return Ok(());
+    }
+    if let Some(set_id) = &session.draft_set {
+        session.draft_pool = draft_set_members(db, set_id).await?;
+    }
+    Ok(())
+}
+
+/// Saves what an engine action changed about each player and deletes the ones
+/// it dropped. Only the changed fields are written, so a timed out turn can't
+/// roll back a player another request just saved, and pick queues are never
+/// written back, they only change through `set_pick_queue`.
+pub async fn save_players(db: &Store, before: &[DraftUser], after: &[DraftUser]) -> StoreResult<()> {
+    for old in before {
+        match after.iter().find(|p| p.id == old.id) {
+            None => {
+                if let Some(id) = &old.id {
+                    db.remove_user(id).await?;
+                }
+            },
+            Some(p) => {
+                let update = PlayerUpdate::between(old, p);
+                if let (Some(id), false) = (&p.id, update.is_empty()) {
+                    db.update_user(id, &update).await?;
+                }
+            },
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,273 @@
+use crate::models::Record;
+use crate::models::draft::history::DraftEventRecord;
+use crate::models::draft::{DraftRules, DraftSession, DraftUser, PlayerUpdate};
+use crate::models::key::KeyHash;
+use crate::models::pokemon::{DraftSetMember, Pokemon, PokemonDraftSet};
+use crate::store::{
+    DraftStore, StoreError, StoreResult, DRAFT_EVENT_TB, DRAFT_RULES_TB, DRAFT_SESSION_TB,
+    DRAFT_SET_RELATION, DRAFT_SET_TB, DRAFT_USER_RELATION, DRAFT_USER_TB, POKEMON_TB,
+};
+
+use serde::{Deserialize, Serialize};
+
+use surrealdb::{RecordId, Surreal};
+use surrealdb::engine::remote::ws::Client;
+
+impl From<surrealdb::Error> for StoreError {
+    fn from(e: surrealdb::Error) -> StoreError {
+        StoreError::Database(e.to_string())
+    }
+}
+
+#[derive(Serialize)]
+struct NewDraftSet {
+    name: String,
+    owner_key_hash: KeyHash,
+}
+
+pub struct SurrealStore {
+    db: Surreal<Client>,
+}
+
+impl SurrealStore {
+    pub fn new(db: Surreal<Client>) -> SurrealStore {
+        SurrealStore { db }
+    }
+
+    /// Runs a query that selects from the record bound to `$id` and takes its first result.
+    async fn select_one<T>(&self, query: String, id: RecordId) -> StoreResult<Option<T>>
+    where
+        for<'a> T: Deserialize<'a>,
+    {
+        let mut resp = self.db.query(query).bind(("id", id)).await?;
+        Ok(resp.take(0)?)
+    }
+
+    async fn draft_set(&self, id: RecordId, detailed: bool) -> StoreResult<Option<PokemonDraftSet>> {
+        let members = format!(
+            "(SELECT out.dex_id AS dex_id, points, tier FROM ->{DRAFT_SET_RELATION} ORDER BY dex_id ASC) as members"
+        );
+        let query = if !detailed {
+            format!("SELECT name,id,owner_key_hash,array::sort(->{DRAFT_SET_RELATION}.out.dex_id, asc) as pokemon.Ids,{members} FROM $id;")
+        } else {
+            format!("SELECT name,id,owner_key_hash,array::sort(->{DRAFT_SET_RELATION}.out.*, asc) as pokemon.Stats,{members} FROM $id;")
+        };
+
+        self.select_one(query, id).await
+    }
+
+    /// Members already in the set lose their old edge first, so what they're
+    /// worth is whatever the new one says.
+    async fn relate_members(&self, id: &RecordId, members: Vec<DraftSetMember>) -> StoreResult<()> {
+        let dex_ids: Vec<u32> = members.iter().map(|m| m.dex_id).collect();
+        self.db
+            .query(format!(
+                "DELETE {DRAFT_SET_RELATION} WHERE in = $id AND out.dex_id IN $dex_ids; \
+                 FOR $m IN $members {{ \
+                     RELATE $id->{DRAFT_SET_RELATION}->(SELECT VALUE id FROM {POKEMON_TB} WHERE dex_id = $m.dex_id) \
+                     SET points = $m.points, tier = $m.tier; \
+                 }};"
+            ))
+            .bind(("id", id.clone()))
+            .bind(("dex_ids", dex_ids))
+            .bind(("members", members))
+            .await?
+            .check()?;
+        Ok(())
+    }
+}
+
+#[rocket::async_trait]
+impl DraftStore for SurrealStore {
+    async fn get_pokemon(&self, id: &str) -> StoreResult<Option<Pokemon>> {
+        Ok(self.db.select((POKEMON_TB, id)).await?)
+    }
+
+    async fn list_pokemon(&self) -> StoreResult<Vec<Pokemon>> {
+        Ok(self.db.select(POKEMON_TB).await?)
+    }
+
+    async fn list_draft_sets(&self) -> StoreResult<Vec<PokemonDraftSet>> {
+        Ok(self.db.select(DRAFT_SET_TB).await?)
+    }
+
+    async fn get_draft_set(&self, id: &str, detailed: bool) -> StoreResult<Option<PokemonDraftSet>> {
+        self.draft_set(RecordId::from_table_key(DRAFT_SET_TB, id), detailed).await
+    }
+
+    async fn create_draft_set(
+        &self,
+        name: &str,
+        members: Vec<DraftSetMember>,
+        owner_key_hash: KeyHash,
+    ) -> StoreResult<PokemonDraftSet> {
+        let created: Option<Record> = self.db
+            .create(DRAFT_SET_TB)
+            .content(NewDraftSet { name: name.to_string(), owner_key_hash })
+            .await?;
+        let id = created.ok_or(StoreError::NotCreated(DRAFT_SET_TB))?.id;
+        self.relate_members(&id, members).await?;
+
+        self.draft_set(id, false).await?.ok_or(StoreError::NotCreated(DRAFT_SET_TB))
+    }
+
+    async fn rename_draft_set(&self, id: &str, name: &str) -> StoreResult<()> {
+        self.db
+            .query("UPDATE $id SET name = $name;")
+            .bind(("id", RecordId::from_table_key(DRAFT_SET_TB, id)))
+            .bind(("name", name.to_string()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn add_draft_set_members(&self, id: &str, members: Vec<DraftSetMember>) -> StoreResult<()> {
+        self.relate_members(&RecordId::from_table_key(DRAFT_SET_TB, id), members).await
+    }
+
+    async fn remove_draft_set_members(&self, id: &str, dex_ids: Vec<u32>) -> StoreResult<()> {
+        self.db
+            .query(format!("DELETE {DRAFT_SET_RELATION} WHERE in = $id AND out.dex_id IN $dex_ids;"))
+            .bind(("id", RecordId::from_table_key(DRAFT_SET_TB, id)))
+            .bind(("dex_ids", dex_ids))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn delete_draft_set(&self, id: &str) -> StoreResult<()> {
+        self.db
+            .query(format!("DELETE {DRAFT_SET_RELATION} WHERE in = $id; DELETE $id;"))
+            .bind(("id", RecordId::from_table_key(DRAFT_SET_TB, id)))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn get_draft_rules(&self, id: &str) -> StoreResult<Option<DraftRules>> {
+        Ok(self.db.select((DRAFT_RULES_TB, id)).await?)
+    }
+
+    async fn list_draft_rules(&self) -> StoreResult<Vec<DraftRules>> {
+        Ok(self.db.select(DRAFT_RULES_TB).await?)
+    }
+
+    async fn create_draft_rules(&self, rules: DraftRules) -> StoreResult<DraftRules> {
+        let created: Option<DraftRules> = self.db.create(DRAFT_RULES_TB).content(rules).await?;
+        created.ok_or(StoreError::NotCreated(DRAFT_RULES_TB))
+    }
+
+    async fn get_session(&self, id: &str) -> StoreResult<Option<DraftSession>> {
+        let query = format!(
+            "SELECT *,(SELECT * from ->{DRAFT_USER_RELATION}.out ORDER BY order_in_session ASC) as players FROM $id;"
+        );
+
+        self.select_one(query, RecordId::from_table_key(DRAFT_SESSION_TB, id)).await
+    }
+
+    async fn create_session(&self, session: DraftSession) -> StoreResult<DraftSession> {
+        let created: Option<DraftSession> = self.db.create(DRAFT_SESSION_TB).content(session).await?;
+        created.ok_or(StoreError::NotCreated(DRAFT_SESSION_TB))
+    }
+
+    async fn update_session_if(&self, session: &DraftSession, action_count: u32) -> StoreResult<bool> {
+        let id = session.id.clone().ok_or(StoreError::MissingId(DRAFT_SESSION_TB))?;
+
+        // players live on their own table, only the session fields get merged.
+        // Sessions stored before `action_count` existed don't have it yet
+        let mut data = session.clone();
+        data.id = None;
+        data.players = None;
+
+        let updated: Vec<Record> = self.db
+            .query("UPDATE $id MERGE $data WHERE (action_count ?? 0) = $action_count;")
+            .bind(("id", id))
+            .bind(("data", data))
+            .bind(("action_count", action_count))
+            .await?
+            .take(0)?;
+        Ok(!updated.is_empty())
+    }
+
+    async fn sessions_past_deadline(&self, now: u64) -> StoreResult<Vec<String>> {
+        let ids: Vec<String> = self.db
+            .query(format!(
+                "SELECT VALUE record::id(id) FROM {DRAFT_SESSION_TB} \
+                 WHERE draft_state = 'InProgress' AND turn_deadline != NONE AND turn_deadline <= $now;"
+            ))
+            .bind(("now", now))
+            .await?
+            .take(0)?;
+        Ok(ids)
+    }
+
+    async fn create_user(&self, session_id: &str, user: DraftUser) -> StoreResult<DraftUser> {
+        let created: Option<DraftUser> = match user.id.clone() {
+            Some(id) => {
+                let mut data = user;
+                data.id = None;
+                self.db.create(id).content(data).await?
+            },
+            None => self.db.create(DRAFT_USER_TB).content(user).await?,
+        };
+        let created = created.ok_or(StoreError::NotCreated(DRAFT_USER_TB))?;
+        let user_id = created.id.clone().ok_or(StoreError::MissingId(DRAFT_USER_TB))?;
+
+        self.db
+            .query(format!("RELATE $session->{DRAFT_USER_RELATION}->$user;"))
+            .bind(("session", RecordId::from_table_key(DRAFT_SESSION_TB, session_id)))
+            .bind(("user", user_id))
+            .await?
+            .check()?;
+
+        Ok(created)
+    }
+
+    async fn update_user(&self, user_id: &RecordId, update: &PlayerUpdate) -> StoreResult<()> {
+        let _updated: Option<Record> = self.db.update(user_id.clone()).merge(update.clone()).await?;
+        Ok(())
+    }
+
+    async fn set_pick_queue(&self, user_id: &RecordId, queue: &[u32]) -> StoreResult<()> {
+        self.db
+            .query("UPDATE $user SET pick_queue = $queue;")
+            .bind(("user", user_id.clone()))
+            .bind(("queue", queue.to_vec()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn set_key_hash(&self, user_id: &RecordId, key_hash: &KeyHash) -> StoreResult<()> {
+        self.db
+            .query("UPDATE $user SET key_hash = $key_hash;")
+            .bind(("user", user_id.clone()))
+            .bind(("key_hash", key_hash.clone()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn remove_user(&self, user_id: &RecordId) -> StoreResult<()> {
+        self.db
+            .query(format!("DELETE {DRAFT_USER_RELATION} WHERE out = $user; DELETE $user;"))
+            .bind(("user", user_id.clone()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    async fn add_draft_event(&self, event: DraftEventRecord) -> StoreResult<()> {
+        let _created: Option<Record> = self.db.create(DRAFT_EVENT_TB).content(event).await?;
+        Ok(())
+    }
+
+    async fn list_draft_events(&self, session_id: &str) -> StoreResult<Vec<DraftEventRecord>> {
+        let events: Vec<DraftEventRecord> = self.db
+            .query(format!("SELECT * FROM {DRAFT_EVENT_TB} WHERE session = $session ORDER BY seq ASC;"))
+            .bind(("session", RecordId::from_table_key(DRAFT_SESSION_TB, session_id)))
+            .await?
+            .take(0)?;
+        Ok(events)
+    }
+}
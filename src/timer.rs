@@ -0,0 +1,133 @@
+//! Ends turns that ran out of time. A task started at liftoff looks for
+//! sessions past their `turn_deadline` once a second and runs a
+//! [`DraftAction::TurnTimeout`] through the engine for each of them.
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::events::EventHub;
+use crate::models::draft::engine::{self, DraftAction};
+use crate::models::draft::history::{Actor, DraftEventRecord};
+use crate::models::draft::{DraftPhase, DraftSession, TimeoutAction};
+use crate::models::pokedex::Pokedex;
+use crate::store::{self, Store};
+
+use rand::seq::SliceRandom;
+
+use rocket::fairing::AdHoc;
+use rocket::tokio::{self, select};
+
+const TICK: Duration = Duration::from_secs(1);
+
+/// Unix milliseconds, what the engine takes as `now`.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub fn fairing() -> AdHoc {
+    AdHoc::on_liftoff("Turn timer", |rocket| Box::pin(async move {
+        let (db, hub, pokedex) = match (rocket.state::<Store>(), rocket.state::<EventHub>(), rocket.state::<Arc<Pokedex>>()) {
+            (Some(db), Some(hub), Some(pokedex)) => (db.clone(), hub.clone(), pokedex.clone()),
+            _ => return,
+        };
+        let mut shutdown = rocket.shutdown();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TICK);
+            loop {
+                select! {
+                    _ = interval.tick() => expire_turns(&db, &hub, &pokedex, now_ms()).await,
+                    _ = &mut shutdown => break,
+                }
+            }
+        });
+    }))
+}
+
+/// Times out every turn whose deadline is at or before `now`.
+pub async fn expire_turns(db: &Store, hub: &EventHub, pokedex: &Arc<Pokedex>, now: u64) {
+    let due = match db.sessions_past_deadline(now).await {
+        Ok(d) => d,
+        Err(e) => {
+            error!("{}", e);
+            return;
+        },
+    };
+
+    for id in due {
+        if let Err(e) = expire_turn(db, hub, pokedex, &id, now).await {
+            error!("Unable to time out the turn in {}: {}", id, e);
+        }
+    }
+}
+
+async fn expire_turn(db: &Store, hub: &EventHub, pokedex: &Arc<Pokedex>, id: &str, now: u64) -> Result<(), String> {
+    match db.get_session(id).await.map_err(|e| e.to_string())? {
+        Some(session) => time_out(db, hub, pokedex, id, session, now).await,
+        None => Ok(()),
+    }
+}
+
+/// Times out the turn in `session` as it was read. Dropped when another action
+/// was saved since then, the next tick looks at the session again.
+pub async fn time_out(
+    db: &Store,
+    hub: &EventHub,
+    pokedex: &Arc<Pokedex>,
+    id: &str,
+    mut session: DraftSession,
+    now: u64,
+) -> Result<(), String> {
+    let auto_select = match session.draft_rules.on_timeout {
+        TimeoutAction::AutoSelect => {
+            store::load_draft_pool(db, &mut session).await.map_err(|e| e.to_string())?;
+            session.pokedex = pokedex.clone();
+            auto_select(&session)
+        },
+        TimeoutAction::Skip => None,
+    };
+    let (before, turn) = (session.players.clone().unwrap_or_default(), session.turn_ticker);
+    let action_count = session.action_count;
+
+    let action = DraftAction::TurnTimeout { auto_select };
+    let (session, events) = engine::apply(session, action.clone(), now).map_err(|e| e.to_string())?;
+
+    if !db.update_session_if(&session, action_count).await.map_err(|e| e.to_string())? {
+        return Ok(());
+    }
+    store::save_players(db, &before, session.players.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Some(record) = DraftEventRecord::new(turn, &session, &action, Actor::Timer, now, &events) {
+        db.add_draft_event(record).await.map_err(|e| e.to_string())?;
+    }
+    hub.publish(id, Some(session.action_count), events);
+
+    Ok(())
+}
+
+/// What the idle player would have chosen. Picks take the top of their queue,
+/// bans stay off it. Anything else is a random pokemon nobody has picked or
+/// banned yet, and for a pick one that fits on the player's roster.
+fn auto_select(session: &DraftSession) -> Option<u32> {
+    let user_id = session.current_player.as_ref()?;
+    let queue = session.get_player(user_id).map(|p| p.pick_queue()).unwrap_or_default();
+
+    let pool = session.draft_pool.as_deref().unwrap_or_default();
+    let available: Vec<u32> = pool.iter().map(|m| m.dex_id).filter(|id| !session.is_pokemon_chosen(id)).collect();
+
+    match session.current_phase {
+        DraftPhase::Pick => session.next_queued(user_id).or_else(|| {
+            let allowed: Vec<u32> = available.iter().copied().filter(|id| session.can_pick(user_id, id)).collect();
+            allowed.choose(&mut rand::thread_rng()).copied()
+        }),
+        DraftPhase::Ban => {
+            let unwanted: Vec<u32> = available.iter().copied().filter(|id| !queue.contains(id)).collect();
+            unwanted.choose(&mut rand::thread_rng())
+                .or_else(|| available.choose(&mut rand::thread_rng()))
+                .copied()
+        },
+    }
+}
@@ -65,7 +65,10 @@ def create_draft_session(set_id, rules_id, draft_name):
 def check_draft_update(session_id):
     draft_session_url = f"{API_URL}/draft_session/{session_id}/update"
     res = requests.get(draft_session_url)
-    return res.json(), res.status_code
+    data = res.json()
+    # only there for matching up live events, it changes with every action
+    data.pop("action_count", None)
+    return data, res.status_code
 
 def create_player(session, player_name):
     draft_session_url = f"{API_URL}/draft_session/{session}/create-user"
@@ -80,15 +83,19 @@ def create_player(session, player_name):
     else:
         return res.json()
 
+def sel(pokemon_id, player):
+    return {"pokemon_id": pokemon_id, "player": player}
+
+def auth_header(player):
+    return {"Authorization": f"Bearer {player['token']}"}
+
 def player_select_pokemon(session, player, pokemon, action="SELECT"):
     select_pokemon_url = f"{API_URL}/draft_session/{session}/select-pokemon"
     post_data = {
-        "user_id": player['user_id'],
         "pokemon_id": pokemon,
         "action": action,
-        "secret": player['key'],
     }
-    res = requests.post(select_pokemon_url, json=post_data)
+    res = requests.post(select_pokemon_url, json=post_data, headers=auth_header(player))
     return res.json(), res.status_code
 
 def player_ban_pokemon(session, player, pokemon):
@@ -99,18 +106,12 @@ def player_pick_pokemon(session, player, pokemon):
 
 def toggle_user(session, player):
     toggle_url = f"{API_URL}/draft_session/{session}/ready"
-    post_data = {
-        "user_id": unwrap_id(player['user_id'])
-    }
-    res = requests.post(toggle_url, json=post_data)
+    res = requests.post(toggle_url, json={}, headers=auth_header(player))
     return res.json(), res.status_code
 
-def start_session(session, player):
+def start_session(session, host):
     toggle_url = f"{API_URL}/draft_session/{session}/start"
-    post_data = {
-        "user_id": unwrap_id(player['user_id'])
-    }
-    res = requests.post(toggle_url, json=post_data)
+    res = requests.post(toggle_url, json={}, headers=auth_header(host))
     return res.json(), res.status_code
 
 # TESTS
@@ -121,6 +122,7 @@ def test_create_session_4_player_join_select_snake():
     if session == "":
         return
     else:
+        host = {"token": session['host_token']}
         session = unwrap_id(session['id'])
 
     players = []
@@ -139,27 +141,27 @@ def test_create_session_4_player_join_select_snake():
 
     for player in players:
         toggle_url = f"{API_URL}/draft_session/{session}/ready"
-        post_data = {
-            "user_id": unwrap_id(player['user_id'])
-        }
-        res = requests.post(toggle_url, json=post_data)
+        res = requests.post(toggle_url, json={}, headers=auth_header(player))
         assert res.status_code == 200
 
     res_data, status = player_ban_pokemon(session, players[0], DEBUG_POKEMON_SET[0])
-    assert status == 404, f"{status}"
+    assert status == 409, f"{status}"
     assert res_data == {
-            "message": "Draft has not yet started"
+            "code": "draft_not_started",
+            "message": "Draft has not yet started",
+            "details": None,
     }, f"{res_data}"
     print(f"Passed: Tried banning pokemon before draft has started")
 
-    res_data, status = start_session(session, players[0])
+    res_data, status = start_session(session, host)
     assert status == 200, f"{res_data}"
 
     res_data, status = player_ban_pokemon(session, players[0], DEBUG_POKEMON_SET[0])
     assert status == 200, f"{res_data}"
     assert res_data == {
         "phase": "Ban",
-        "banned_pokemon": [1],
+        "banned": [sel(1, "Player 1")],
+        "picked": [],
         "selected_pokemon": [],
     }, f"{res_data}"
     print(f"Passed: {players[0]['name']} banning pokemon 1.")
@@ -168,7 +170,8 @@ def test_create_session_4_player_join_select_snake():
     assert status == 200, f"{res_data}"
     assert res_data == {
         "phase": "Ban",
-        "banned_pokemon": [1, 2],
+        "banned": [sel(1, "Player 1"), sel(2, "Player 2")],
+        "picked": [],
         "selected_pokemon": [],
     }, f"{res_data}"
     print(f"Passed: {players[1]['name']} banning pokemon 2.")
@@ -177,7 +180,8 @@ def test_create_session_4_player_join_select_snake():
     assert status == 200, f"{res_data}"
     assert res_data == {
         "phase": "Ban",
-        "banned_pokemon": [1, 2, 3],
+        "banned": [sel(1, "Player 1"), sel(2, "Player 2"), sel(3, "Player 3")],
+        "picked": [],
         "selected_pokemon": [],
     }, f"{res_data}"
     print(f"Passed: {players[2]['name']} banning pokemon 3.")
@@ -186,33 +190,39 @@ def test_create_session_4_player_join_select_snake():
     assert status == 200, f"{res_data}"
     assert res_data == {
         "phase": "Pick",
-        "banned_pokemon": [1, 2, 3, 4],
+        "banned": [sel(1, "Player 1"), sel(2, "Player 2"), sel(3, "Player 3"), sel(4, "Player 4")],
+        "picked": [],
         "selected_pokemon": [],
     }, f"{res_data}"
     print(f"Passed: {players[3]['name']} banning pokemon 4.")
 
     res_data, status = player_ban_pokemon(session, players[0], DEBUG_POKEMON_SET[4])
-    assert status == 404, f"{res_data}"
-    assert res_data == { "message": "Current action not allowed" }, f"{res_data}"
+    assert status == 409, f"{res_data}"
+    assert res_data == {
+            "code": "action_not_allowed",
+            "message": "Current action not allowed",
+            "details": { "current_phase": "Pick" },
+    }, f"{res_data}"
     print(f"Passed: {players[0]['name']} failing to pick pokemon 5.")
 
     res_data, status = player_pick_pokemon(session, players[0], DEBUG_POKEMON_SET[4])
-    assert status == 404, f"{res_data}"
-    assert res_data == { "message": "It is not your turn" }, f"{res_data}"
+    assert status == 403, f"{res_data}"
+    assert res_data["code"] == "not_your_turn", f"{res_data}"
     print(f"Passed: {players[0]['name']} failing to pick pokemon 5.")
 
     res_data, status = player_pick_pokemon(session, players[3], DEBUG_POKEMON_SET[4])
     assert status == 200, f"{res_data}"
     assert res_data == {
         "phase": "Pick",
-        "banned_pokemon": [1, 2, 3, 4, 5],
+        "banned": [sel(1, "Player 1"), sel(2, "Player 2"), sel(3, "Player 3"), sel(4, "Player 4")],
+        "picked": [sel(5, "Player 4")],
         "selected_pokemon": [5],
     }, f"{res_data}"
     print(f"Passed: {players[3]['name']} picking pokemon 5.")
 
     res_data, status = check_draft_update(session)
     assert status == 200, f"{res_data}"
-    assert res_data == {'current_phase': 'Pick', 'banned_pokemon': [1, 2, 3, 4, 5], 'current_player': 'Player 3', 'state': 'InProgress','players': [{'name': 'Player 1', 'pokemon': [], "ready": True}, {'name': 'Player 2', 'pokemon': [], "ready": True}, {'name': 'Player 3', 'pokemon': [], "ready": True}, {'name': 'Player 4', 'pokemon': [5], "ready": True}]}, f"{res_data}"
+    assert res_data == {'current_phase': 'Pick', 'banned': [sel(1, "Player 1"), sel(2, "Player 2"), sel(3, "Player 3"), sel(4, "Player 4")], 'picked': [sel(5, "Player 4")], 'current_player': 'Player 3', 'state': 'InProgress','players': [{'name': 'Player 1', 'pokemon': [], "ready": True}, {'name': 'Player 2', 'pokemon': [], "ready": True}, {'name': 'Player 3', 'pokemon': [], "ready": True}, {'name': 'Player 4', 'pokemon': [5], "ready": True}]}, f"{res_data}"
 
 @test
 def test_toggle_ready_on_pokemon():
@@ -221,6 +231,7 @@ def test_toggle_ready_on_pokemon():
     if session == "":
         return
     else:
+        host = {"token": session['host_token']}
         session = unwrap_id(session['id'])
 
     players = []
@@ -238,42 +249,42 @@ def test_toggle_ready_on_pokemon():
             return False
     
     res_data, status = check_draft_update(session)
-    assert res_data == {'current_phase': 'Ban', 'banned_pokemon': [], 'current_player': 'Player 1', 'state': 'Open', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': False},{'name': 'Player 2', 'pokemon': [], 'ready': False}, {'name': 'Player 3', 'pokemon': [], 'ready': False}]}, f"{res_data}"
+    assert res_data == {'current_phase': 'Ban', 'picked': [], 'banned': [], 'current_player': 'Player 1', 'state': 'Open', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': False},{'name': 'Player 2', 'pokemon': [], 'ready': False}, {'name': 'Player 3', 'pokemon': [], 'ready': False}]}, f"{res_data}"
     print("Passed: All players ready status is set to false.")
 
     res_data, status = toggle_user(session, players[0])
     assert status == 200, f"{res_data}"
     res_data, status = check_draft_update(session)
     assert status == 200, f"{res_data}"
-    assert res_data == {'current_phase': 'Ban', 'banned_pokemon': [], 'current_player': 'Player 1', 'state': 'Open', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': True},{'name': 'Player 2', 'pokemon': [], 'ready': False}, {'name': 'Player 3', 'pokemon': [], 'ready': False}]}, f"{res_data}"
+    assert res_data == {'current_phase': 'Ban', 'picked': [], 'banned': [], 'current_player': 'Player 1', 'state': 'Open', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': True},{'name': 'Player 2', 'pokemon': [], 'ready': False}, {'name': 'Player 3', 'pokemon': [], 'ready': False}]}, f"{res_data}"
     print("Passed: Setting Player 1 Ready Status")
 
     res_data, status = toggle_user(session, players[1])
     assert status == 200, f"{res_data}"
     res_data, status = check_draft_update(session)
     assert status == 200, f"{res_data}"
-    assert res_data == {'current_phase': 'Ban', 'banned_pokemon': [], 'current_player': 'Player 1','state':'Open', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': True},{'name': 'Player 2', 'pokemon': [], 'ready': True}, {'name': 'Player 3', 'pokemon': [], 'ready': False}]}, f"{res_data}"
+    assert res_data == {'current_phase': 'Ban', 'picked': [], 'banned': [], 'current_player': 'Player 1','state':'Open', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': True},{'name': 'Player 2', 'pokemon': [], 'ready': True}, {'name': 'Player 3', 'pokemon': [], 'ready': False}]}, f"{res_data}"
     print("Passed: Setting Player 2 Ready Status")
 
     res_data, status = toggle_user(session, players[2])
     assert status == 200, f"{res_data}"
     res_data, status = check_draft_update(session)
     assert status == 200, f"{res_data}"
-    assert res_data == {'current_phase': 'Ban', 'banned_pokemon': [], 'current_player': 'Player 1','state':'Ready', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': True},{'name': 'Player 2', 'pokemon': [], 'ready': True}, {'name': 'Player 3', 'pokemon': [], 'ready': True}]}, f"{res_data}"
+    assert res_data == {'current_phase': 'Ban', 'picked': [], 'banned': [], 'current_player': 'Player 1','state':'Ready', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': True},{'name': 'Player 2', 'pokemon': [], 'ready': True}, {'name': 'Player 3', 'pokemon': [], 'ready': True}]}, f"{res_data}"
     print("Passed: Setting Player 3 Ready Status")
 
     res_data, status = toggle_user(session, players[1])
     assert status == 200, f"{res_data}"
     res_data, status = check_draft_update(session)
     assert status == 200, f"{res_data}"
-    assert res_data == {'current_phase': 'Ban', 'banned_pokemon': [], 'current_player': 'Player 1','state':'Open', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': True},{'name': 'Player 2', 'pokemon': [], 'ready': False}, {'name': 'Player 3', 'pokemon': [], 'ready': True}]}, f"{res_data}"
+    assert res_data == {'current_phase': 'Ban', 'picked': [], 'banned': [], 'current_player': 'Player 1','state':'Open', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': True},{'name': 'Player 2', 'pokemon': [], 'ready': False}, {'name': 'Player 3', 'pokemon': [], 'ready': True}]}, f"{res_data}"
     print("Passed: Setting Player 1 Ready Status")
 
     res_data, status = toggle_user(session, players[1])
     assert status == 200, f"{res_data}"
     res_data, status = check_draft_update(session)
     assert status == 200, f"{res_data}"
-    assert res_data == {'current_phase': 'Ban', 'banned_pokemon': [], 'current_player': 'Player 1','state':'Ready', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': True},{'name': 'Player 2', 'pokemon': [], 'ready': True}, {'name': 'Player 3', 'pokemon': [], 'ready': True}]}, f"{res_data}"
+    assert res_data == {'current_phase': 'Ban', 'picked': [], 'banned': [], 'current_player': 'Player 1','state':'Ready', 'players': [{'name': 'Player 1', 'pokemon': [], 'ready': True},{'name': 'Player 2', 'pokemon': [], 'ready': True}, {'name': 'Player 3', 'pokemon': [], 'ready': True}]}, f"{res_data}"
     print("Passed: Setting Player 1 Ready Status")
 
 
@@ -284,6 +295,7 @@ def test_full_game():
     if session == "":
         return
     else:
+        host = {"token": session['host_token']}
         session = unwrap_id(session['id'])
 
     players = []
@@ -304,7 +316,7 @@ def test_full_game():
 
     print("Passed: Created all users and toggled them.")
 
-    res_data, status = start_session(session, players[0])
+    res_data, status = start_session(session, host)
     assert status == 200, f"{res_data}"
     print("Passed: Started draft session.")
 
@@ -333,12 +345,12 @@ def test_full_game():
     print(f"Passed: {players[0]['name']} picked {DEBUG_POKEMON_SET[5]}")
 
     res_data, status = player_ban_pokemon(session, players[0], DEBUG_POKEMON_SET[6])
-    assert status == 404, f"{res_data}"
+    assert status == 409, f"{res_data}"
     print(f"Passed: {players[0]['name']} failed in banning {DEBUG_POKEMON_SET[6]}")
 
     res_data, status = check_draft_update(session)
     assert status == 200, f"{res_data}"
-    assert res_data == {'current_phase': 'Ban', 'banned_pokemon': [1,2,3,4,5,6], 'current_player': 'Player 1','state':'Ended', 'players': [{'name': 'Player 1', 'pokemon': [6], 'ready': True},{'name': 'Player 2', 'pokemon': [5], 'ready': True}, {'name': 'Player 3', 'pokemon': [4], 'ready': True}]}, f"{res_data}"
+    assert res_data == {'current_phase': 'Pick', 'banned': [sel(1, "Player 1"), sel(2, "Player 2"), sel(3, "Player 3")], 'picked': [sel(4, "Player 3"), sel(5, "Player 2"), sel(6, "Player 1")], 'current_player': None,'state':'Ended', 'players': [{'name': 'Player 1', 'pokemon': [6], 'ready': True},{'name': 'Player 2', 'pokemon': [5], 'ready': True}, {'name': 'Player 3', 'pokemon': [4], 'ready': True}]}, f"{res_data}"
     print("Passed: Checking state update")
 
 @test
@@ -348,6 +360,7 @@ def test_full_game_pick_first():
     if session == "":
         return
     else:
+        host = {"token": session['host_token']}
         session = unwrap_id(session['id'])
 
     players = []
@@ -368,7 +381,7 @@ def test_full_game_pick_first():
 
     print("Passed: Created all users and toggled them.")
 
-    res_data, status = start_session(session, players[0])
+    res_data, status = start_session(session, host)
     assert status == 200, f"{res_data}"
     print("Passed: Started draft session.")
 
@@ -385,12 +398,12 @@ def test_full_game_pick_first():
     print(f"Passed: {players[2]['name']} banned {DEBUG_POKEMON_SET[2]}")
 
     res_data, status = player_ban_pokemon(session, players[2], DEBUG_POKEMON_SET[3])
-    assert status == 404, f"{res_data}"
+    assert status == 409, f"{res_data}"
     print(f"Passed: {players[2]['name']} picked {DEBUG_POKEMON_SET[3]}")
 
     res_data, status = check_draft_update(session)
     assert status == 200, f"{res_data}"
-    assert res_data == {'current_phase': 'Ban', 'banned_pokemon': [1,2,3], 'current_player': 'Player 3','state':'Ended', 'players': [{'name': 'Player 1', 'pokemon': [1], 'ready': True},{'name': 'Player 2', 'pokemon': [2], 'ready': True}, {'name': 'Player 3', 'pokemon': [3], 'ready': True}]}, f"{res_data}"
+    assert res_data == {'current_phase': 'Pick', 'banned': [], 'picked': [sel(1, "Player 1"), sel(2, "Player 2"), sel(3, "Player 3")], 'current_player': None,'state':'Ended', 'players': [{'name': 'Player 1', 'pokemon': [1], 'ready': True},{'name': 'Player 2', 'pokemon': [2], 'ready': True}, {'name': 'Player 3', 'pokemon': [3], 'ready': True}]}, f"{res_data}"
     print("Passed: Setting Player 1 Ready Status")
 
 def extra():